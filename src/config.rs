@@ -0,0 +1,136 @@
+//!
+//! Reader of simple `key = value` configs with INI-style `[section]` headers
+//!
+//! ``` rust
+//!     use rcin::RInStream;
+//!     use rcin::config;
+//!
+//!     let text = "name = demo\n\n[server]\n# local only\nhost = 127.0.0.1\nport = 8080\n[client]\n";
+//!     let mut reader = RInStream::from_source(Box::new(text.as_bytes()));
+//!     let config = config::read_config(&mut reader).unwrap();
+//!     assert_eq!(config.get("", "name"), Some("demo"));
+//!     assert_eq!(config.get("server", "host"), Some("127.0.0.1"));
+//!     assert_eq!(config.parse::<u16>("server", "port"), Some(8080));
+//!     assert_eq!(config.sections().collect::<Vec<_>>(), ["", "server", "client"]);
+//! ```
+//!
+
+use crate::{RcinRead, ReadError};
+use alloc::collections::BTreeMap;
+#[cfg(not(feature = "std"))]
+use alloc::{string::String, vec::Vec};
+use core::fmt;
+use core::str::FromStr;
+
+/// Line of a config that is neither a pair, a header nor a comment
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConfigError {
+    /// Line number, starting at 1
+    pub line: usize,
+    /// Text of the line
+    pub text: String,
+}
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid config line {}: '{}'", self.line, self.text)
+    }
+}
+#[cfg(feature = "std")]
+impl std::error::Error for ConfigError {}
+
+/// Pairs by section, the ones before the first header are in section `""`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Config {
+    sections: BTreeMap<String, BTreeMap<String, String>>,
+    order: Vec<String>, // section names as they first appear
+}
+
+impl Config {
+    /// Value of a key, a repeated key keeps the last value
+    pub fn get(&self, section: &str, key: &str) -> Option<&str> {
+        self.sections.get(section)?.get(key).map(String::as_str)
+    }
+    /// Value of a key parsed as T
+    pub fn parse<T: FromStr>(&self, section: &str, key: &str) -> Option<T> {
+        self.get(section, key)?.parse().ok()
+    }
+    /// All pairs of a section
+    pub fn section(&self, name: &str) -> Option<&BTreeMap<String, String>> {
+        self.sections.get(name)
+    }
+    /// Names of all sections in the order they first appear
+    pub fn sections(&self) -> impl Iterator<Item = &str> {
+        self.order.iter().map(String::as_str)
+    }
+    fn section_mut(&mut self, name: &str) -> &mut BTreeMap<String, String> {
+        if !self.sections.contains_key(name) {
+            self.order.push(String::from(name));
+        }
+        self.sections.entry(String::from(name)).or_default()
+    }
+}
+
+/// Read `key = value` lines up to a `[section]` header or the end, the header is left unread
+///
+/// Empty lines and lines starting with `#` or `;` are skipped, values in double quotes are unquoted
+pub fn read_kv_pairs(reader: &mut impl RcinRead) -> Result<Vec<(String, String)>, ConfigError> {
+    let mut pairs = Vec::new();
+    while reader.peek_token().is_some() && !at_header(reader) {
+        let line_no = reader.position().line;
+        let line = match reader.read_line_checked() {
+            Ok(line) => line,
+            Err(ReadError::Eof) => break,
+            Err(_) => return Err(ConfigError { line: line_no, text: String::new() }),
+        };
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with(['#', ';']) {
+            continue;
+        }
+        match trimmed.split_once('=') {
+            Some((key, value)) if !key.trim().is_empty() => {
+                let value = value.trim();
+                let value = value
+                    .strip_prefix('"')
+                    .and_then(|v| v.strip_suffix('"'))
+                    .unwrap_or(value);
+                pairs.push((String::from(key.trim()), String::from(value)));
+            }
+            _ => return Err(ConfigError { line: line_no, text: line }),
+        }
+    }
+    Ok(pairs)
+}
+
+/// Read a whole config with sections
+pub fn read_config(reader: &mut impl RcinRead) -> Result<Config, ConfigError> {
+    let mut config = Config::default();
+    let mut section = String::new();
+    loop {
+        let pairs = read_kv_pairs(reader)?;
+        if !pairs.is_empty() {
+            config.section_mut(&section).extend(pairs);
+        }
+        if reader.peek_token().is_none() {
+            return Ok(config);
+        }
+        // the header comes after the empty lines
+        let (line_no, line) = loop {
+            let line_no = reader.position().line;
+            match reader.read_line_checked() {
+                Ok(line) if line.trim().is_empty() => continue,
+                line => break (line_no, line.unwrap_or_default()),
+            }
+        };
+        section = match line.trim().strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            Some(name) => String::from(name.trim()),
+            None => return Err(ConfigError { line: line_no, text: line }),
+        };
+        // an empty section is still listed
+        config.section_mut(&section);
+    }
+}
+
+// the next token starts a section header
+fn at_header(reader: &mut impl RcinRead) -> bool {
+    reader.peek_token().is_some_and(|t| t.starts_with('['))
+}
@@ -0,0 +1,117 @@
+//!
+//! Windows console input read as utf16 with `ReadConsoleW` and handed out as utf8
+//!
+
+use core::ffi::c_void;
+use std::fs::File;
+use std::io;
+use std::os::windows::io::AsRawHandle;
+
+const STD_INPUT_HANDLE: u32 = -10i32 as u32;
+const UNITS: usize = 4096; // utf16 units read at once
+const CTRL_Z: u16 = 0x1a;
+
+#[link(name = "kernel32")]
+extern "system" {
+    fn GetStdHandle(std_handle: u32) -> *mut c_void;
+    fn GetConsoleMode(console: *mut c_void, mode: *mut u32) -> i32;
+    fn ReadConsoleW(console: *mut c_void, buf: *mut c_void, to_read: u32, read: *mut u32, control: *mut c_void) -> i32;
+}
+
+pub(crate) struct Console {
+    handle: *mut c_void,
+    _file: Option<File>, // owns the handle of CONIN$, stdin isn't closed
+    units: Vec<u16>,
+    high: Option<u16>, // high surrogate read without its low half
+    ready: Vec<u8>,    // utf8 not handed out yet
+    start: usize,
+    ended: bool,
+}
+
+// safety: the handle is only used by the owner of Console
+unsafe impl Send for Console {}
+
+impl Console {
+    // stdin if it's attached to a console, pipes and files are read as bytes
+    pub(crate) fn stdin() -> Option<Self> {
+        // safety: GetStdHandle has no preconditions
+        let handle = unsafe { GetStdHandle(STD_INPUT_HANDLE) };
+        Self::new(handle, None)
+    }
+    // the console of the process, even if stdin is redirected
+    pub(crate) fn conin() -> io::Result<Self> {
+        let file = File::options().read(true).write(true).open("CONIN$")?;
+        let handle = file.as_raw_handle();
+        Self::new(handle, Some(file)).ok_or_else(|| io::Error::other("CONIN$ is no console"))
+    }
+    fn new(handle: *mut c_void, file: Option<File>) -> Option<Self> {
+        let mut mode = 0;
+        // safety: GetConsoleMode fails on handles that aren't a console
+        if handle.is_null() || unsafe { GetConsoleMode(handle, &mut mode) } == 0 {
+            return None;
+        }
+        Some(Console {
+            handle,
+            _file: file,
+            units: vec![0; UNITS],
+            high: None,
+            ready: Vec::new(),
+            start: 0,
+            ended: false,
+        })
+    }
+    // read the next units and convert them to utf8, 0 at the end of input
+    fn fill(&mut self) -> io::Result<usize> {
+        let mut read = 0;
+        // safety: units stays valid for the whole call and read is within its length
+        let ok = unsafe {
+            ReadConsoleW(
+                self.handle,
+                self.units.as_mut_ptr().cast(),
+                UNITS as u32,
+                &mut read,
+                core::ptr::null_mut(),
+            )
+        };
+        if ok == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let mut units = &self.units[..read as usize];
+        // ctrl-z at the start of a line ends the input like in cmd
+        if units.first() == Some(&CTRL_Z) {
+            self.ended = true;
+            units = &[];
+        }
+        self.ready.clear();
+        self.start = 0;
+        let mut all: Vec<u16> = self.high.take().into_iter().chain(units.iter().copied()).collect();
+        // the low half of a pair split between reads comes with the next one
+        if !self.ended && matches!(all.last(), Some(0xD800..=0xDBFF)) {
+            self.high = all.pop();
+        }
+        let mut tmp = [0; 4];
+        for c in char::decode_utf16(all) {
+            let c = c.unwrap_or(char::REPLACEMENT_CHARACTER);
+            self.ready.extend_from_slice(c.encode_utf8(&mut tmp).as_bytes());
+        }
+        Ok(self.ready.len())
+    }
+}
+
+impl io::Read for Console {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.start == self.ready.len() {
+            if self.ended {
+                return Ok(0);
+            }
+            // nothing read and no half of a pair waiting, like a closed console
+            if self.fill()? == 0 && self.high.is_none() {
+                self.ended = true;
+            }
+        }
+        let n = buf.len().min(self.ready.len() - self.start);
+        buf[..n].copy_from_slice(&self.ready[self.start..self.start + n]);
+        self.start += n;
+        Ok(n)
+    }
+}
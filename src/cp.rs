@@ -0,0 +1,70 @@
+//!
+//! Helpers for common competitive programming inputs
+//!
+//! ``` rust
+//!     use rcin::RInStream;
+//!     use rcin::cp::{self, Indexing};
+//!
+//!     let mut reader = RInStream::from_source(Box::new(&b"3 2\n1 2\n2 3\n"[..]));
+//!     let (n, m) = (reader.read().unwrap(), reader.read().unwrap());
+//!     let adj = cp::read_graph(&mut reader, n, m, Indexing::OneBased).unwrap();
+//!     assert_eq!(adj, vec![vec![1], vec![0, 2], vec![1]]);
+//! ```
+//!
+
+use crate::RcinRead;
+#[cfg(not(feature = "std"))]
+use alloc::{vec, vec::Vec};
+
+/// Vertex numbering used by the input
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Indexing {
+    /// Vertices are numbered 0..n
+    ZeroBased,
+    /// Vertices are numbered 1..=n, they are converted to 0..n
+    OneBased,
+}
+
+impl Indexing {
+    fn convert(self, v: usize) -> Option<usize> {
+        match self {
+            Indexing::ZeroBased => Some(v),
+            Indexing::OneBased => v.checked_sub(1),
+        }
+    }
+}
+
+/// Read m edges given as pairs of vertices
+pub fn read_edges(reader: &mut impl RcinRead, m: usize, indexing: Indexing) -> Option<Vec<(usize, usize)>> {
+    (0..m)
+        .map(|_| {
+            let u = indexing.convert(reader.read()?)?;
+            let v = indexing.convert(reader.read()?)?;
+            Some((u, v))
+        })
+        .collect()
+}
+
+/// Read m undirected edges into an adjacency list of n vertices
+pub fn read_graph(reader: &mut impl RcinRead, n: usize, m: usize, indexing: Indexing) -> Option<Vec<Vec<usize>>> {
+    build_adjacency(read_edges(reader, m, indexing)?, n, false)
+}
+
+/// Read m directed edges into an adjacency list of n vertices
+pub fn read_digraph(reader: &mut impl RcinRead, n: usize, m: usize, indexing: Indexing) -> Option<Vec<Vec<usize>>> {
+    build_adjacency(read_edges(reader, m, indexing)?, n, true)
+}
+
+fn build_adjacency(edges: Vec<(usize, usize)>, n: usize, directed: bool) -> Option<Vec<Vec<usize>>> {
+    let mut adj = vec![Vec::new(); n];
+    for (u, v) in edges {
+        if u >= n || v >= n {
+            return None;
+        }
+        adj[u].push(v);
+        if !directed {
+            adj[v].push(u);
+        }
+    }
+    Some(adj)
+}
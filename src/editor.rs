@@ -0,0 +1,452 @@
+//!
+//! Raw mode line editor used by `rin.read_line` on terminals
+//!
+
+use crate::interactive::{History, Interrupt};
+use crossterm::cursor::{self, MoveToColumn, MoveUp};
+use crossterm::event::{
+    self, DisableBracketedPaste, EnableBracketedPaste, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers,
+};
+use crossterm::terminal::{self, Clear, ClearType};
+use crossterm::{execute, queue};
+use std::convert::TryFrom;
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+/// Candidates for the text before the cursor, each replaces it completely
+pub(crate) type Completer = Box<dyn Fn(&str) -> Vec<String> + Send>;
+
+// leaves raw mode even if reading panics
+pub(crate) struct RawMode {
+    paste: bool, // bracketed paste is enabled
+}
+impl RawMode {
+    pub(crate) fn enable() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        Ok(RawMode { paste: false })
+    }
+    // pasted text comes between escape sequences instead of as typed keys
+    pub(crate) fn with_paste() -> io::Result<Self> {
+        let mut raw = Self::enable()?;
+        execute!(io::stdout(), EnableBracketedPaste)?;
+        raw.paste = true;
+        Ok(raw)
+    }
+}
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        if self.paste {
+            let _ = execute!(io::stdout(), DisableBracketedPaste);
+        }
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+// what a key did to the line
+#[derive(Debug, PartialEq)]
+enum Action {
+    Edited, // text or cursor changed, the line needs a redraw
+    Typed(char),
+    Erased, // last char removed
+    Ignored,
+    Complete, // tab, left to the completer
+    Submit,
+    Stop(Interrupt),
+}
+
+// line being edited after the prompt that was already printed
+struct Line {
+    chars: Vec<char>,
+    cursor: usize,
+    start: u16,              // column where the line starts
+    recalled: Option<usize>, // history entry, counted from the newest
+    draft: String,           // text typed before recalling
+}
+impl Line {
+    fn new(start: u16, text: &str) -> Self {
+        let mut line = Line {
+            chars: Vec::new(),
+            cursor: 0,
+            start,
+            recalled: None,
+            draft: String::new(),
+        };
+        line.set(text);
+        line
+    }
+    fn set(&mut self, text: &str) {
+        self.chars = text.chars().collect();
+        self.cursor = self.chars.len();
+    }
+    fn redraw(&self, out: &mut impl Write) -> io::Result<()> {
+        let text: String = self.chars.iter().collect();
+        let cursor = self.start as usize + self.cursor;
+        queue!(out, MoveToColumn(self.start), Clear(ClearType::UntilNewLine))?;
+        write!(out, "{}", text)?;
+        queue!(out, MoveToColumn(u16::try_from(cursor).unwrap_or(u16::MAX)))?;
+        out.flush()
+    }
+    fn key(&mut self, key: KeyEvent, history: Option<&History>) -> Action {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Enter => return Action::Submit,
+            KeyCode::Char('c') if ctrl => return Action::Stop(Interrupt::Interrupted),
+            KeyCode::Char('d') if ctrl && self.chars.is_empty() => return Action::Stop(Interrupt::Eof),
+            KeyCode::Char('a') if ctrl => self.cursor = 0,
+            KeyCode::Char('e') if ctrl => self.cursor = self.chars.len(),
+            KeyCode::Char(c) if !ctrl => {
+                self.chars.insert(self.cursor, c);
+                self.cursor += 1;
+            }
+            KeyCode::Backspace if self.cursor > 0 => {
+                self.cursor -= 1;
+                self.chars.remove(self.cursor);
+            }
+            KeyCode::Delete if self.cursor < self.chars.len() => {
+                self.chars.remove(self.cursor);
+            }
+            KeyCode::Left => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::Right => self.cursor = (self.cursor + 1).min(self.chars.len()),
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::End => self.cursor = self.chars.len(),
+            KeyCode::Tab => return Action::Complete,
+            KeyCode::Up | KeyCode::Down => self.recall(key.code == KeyCode::Up, history),
+            _ => return Action::Ignored,
+        }
+        Action::Edited
+    }
+    // step to an older or newer history entry, past the newest the draft comes back
+    fn recall(&mut self, older: bool, history: Option<&History>) {
+        let entries = history.map_or(0, History::len);
+        let target = match (older, self.recalled) {
+            (true, None) if entries > 0 => Some(0),
+            (true, Some(i)) => Some((i + 1).min(entries - 1)),
+            (false, Some(0)) => None,
+            (false, Some(i)) => Some(i - 1),
+            _ => self.recalled,
+        };
+        if self.recalled.is_none() {
+            self.draft = self.chars.iter().collect();
+        }
+        match target.and_then(|i| history?.iter().rev().nth(i)) {
+            Some(entry) => self.set(entry),
+            None => self.set(&self.draft.clone()),
+        }
+        self.recalled = target;
+    }
+    // replace the text before the cursor with the common prefix of candidates, list them if it doesn't grow
+    fn complete(&mut self, completer: &Completer, out: &mut impl Write) -> io::Result<()> {
+        let before: String = self.chars[..self.cursor].iter().collect();
+        let candidates = completer(&before);
+        let prefix = match candidates.split_first() {
+            Some((first, rest)) => rest.iter().fold(first.as_str(), |prefix, c| common_prefix(prefix, c)),
+            None => return Ok(()),
+        };
+        if prefix.chars().count() > self.cursor || candidates.len() == 1 {
+            let after = self.chars.split_off(self.cursor);
+            self.set(prefix);
+            self.chars.extend(after);
+            return Ok(());
+        }
+        let list = candidates.join("  ");
+        let width = terminal::size().map_or(80, |(w, _)| w.max(1) as usize);
+        let rows = list.chars().count() / width + 1;
+        write!(out, "\r\n{}", list)?;
+        queue!(out, MoveUp(u16::try_from(rows).unwrap_or(u16::MAX)))?;
+        Ok(())
+    }
+}
+
+fn common_prefix<'a>(a: &'a str, b: &str) -> &'a str {
+    let len = a
+        .char_indices()
+        .zip(b.chars())
+        .find(|((_, x), y)| x != y)
+        .map_or(a.len().min(b.len()), |((i, _), _)| i);
+    &a[..len]
+}
+
+// read a line with cursor movement and history recall, ctrl-c and ctrl-d on an empty line stop it
+//
+// pending is the initial text, on return it holds pasted text after the first line break
+pub(crate) fn read_line(
+    history: Option<&mut History>,
+    completer: Option<&Completer>,
+    pending: &mut String,
+) -> io::Result<Result<String, Interrupt>> {
+    let mut out = io::stdout();
+    let _raw = RawMode::with_paste()?;
+    let mut line = Line::new(cursor::position()?.0, pending);
+    pending.clear();
+    if !line.chars.is_empty() {
+        line.redraw(&mut out)?;
+    }
+    loop {
+        let key = match event::read()? {
+            Event::Key(key) if key.kind != KeyEventKind::Release => key,
+            Event::Paste(text) => {
+                let text = text.replace("\r\n", "\n").replace('\r', "\n");
+                let (first, rest) = match text.find('\n') {
+                    Some(i) => (&text[..i], Some(&text[i + 1..])),
+                    None => (text.as_str(), None),
+                };
+                let after = line.chars.split_off(line.cursor);
+                line.chars.extend(first.chars());
+                line.cursor = line.chars.len();
+                match rest {
+                    // the rest of the line follows the pasted lines
+                    Some(rest) => {
+                        pending.push_str(rest);
+                        pending.extend(after);
+                        line.redraw(&mut out)?;
+                        break;
+                    }
+                    None => line.chars.extend(after),
+                }
+                line.redraw(&mut out)?;
+                continue;
+            }
+            _ => continue,
+        };
+        match line.key(key, history.as_deref()) {
+            Action::Submit => break,
+            Action::Stop(interrupt) => {
+                execute!(out, crossterm::style::Print("\r\n"))?;
+                return Ok(Err(interrupt));
+            }
+            Action::Complete => match completer {
+                Some(completer) => line.complete(completer, &mut out)?,
+                None => continue,
+            },
+            Action::Edited => {}
+            _ => continue,
+        }
+        line.redraw(&mut out)?;
+    }
+    execute!(out, crossterm::style::Print("\r\n"))?;
+    let text: String = line.chars.into_iter().collect();
+    if let Some(history) = history {
+        history.push(&text);
+    }
+    Ok(Ok(text))
+}
+
+// keys of a line without cursor movement, ctrl-d on an empty line ends it if eof is set
+fn plain_key(text: &mut String, key: KeyEvent, eof: bool) -> Action {
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    match key.code {
+        KeyCode::Enter => Action::Submit,
+        KeyCode::Char('c') if ctrl => Action::Stop(Interrupt::Interrupted),
+        KeyCode::Char('d') if ctrl && eof && text.is_empty() => Action::Stop(Interrupt::Eof),
+        KeyCode::Char(c) if !ctrl => {
+            text.push(c);
+            Action::Typed(c)
+        }
+        KeyCode::Backspace if text.pop().is_some() => Action::Erased,
+        _ => Action::Ignored,
+    }
+}
+
+// read a line echoing mask for every char, for passwords
+pub(crate) fn read_masked(prompt: &str, mask: char) -> io::Result<Result<String, Interrupt>> {
+    let mut out = io::stdout();
+    write!(out, "{}", prompt)?;
+    out.flush()?;
+    let _raw = RawMode::enable()?;
+    let mut text = String::new();
+    loop {
+        let key = match event::read()? {
+            Event::Key(key) if key.kind != KeyEventKind::Release => key,
+            _ => continue,
+        };
+        match plain_key(&mut text, key, true) {
+            Action::Submit => break,
+            Action::Stop(interrupt) => {
+                execute!(out, crossterm::style::Print("\r\n"))?;
+                return Ok(Err(interrupt));
+            }
+            Action::Typed(_) => write!(out, "{}", mask)?,
+            Action::Erased => write!(out, "\u{8} \u{8}")?,
+            _ => continue,
+        }
+        out.flush()?;
+    }
+    execute!(out, crossterm::style::Print("\r\n"))?;
+    Ok(Ok(text))
+}
+
+// read a line while counting down, an empty line is returned once the time is up before the first key
+pub(crate) fn read_timeout(prompt: &str, timeout: Duration) -> io::Result<Result<String, Interrupt>> {
+    let mut out = io::stdout();
+    let _raw = RawMode::enable()?;
+    let deadline = Instant::now() + timeout;
+    let mut text = String::new();
+    let mut counting = true;
+    let mut shown = None;
+    loop {
+        if counting {
+            let left = deadline.saturating_duration_since(Instant::now());
+            if left.is_zero() {
+                text.clear();
+                break;
+            }
+            let secs = left.as_secs() + u64::from(left.subsec_nanos() > 0);
+            if shown != Some(secs) {
+                queue!(out, MoveToColumn(0), Clear(ClearType::CurrentLine))?;
+                write!(out, "{}({}s) ", prompt, secs)?;
+                out.flush()?;
+                shown = Some(secs);
+            }
+            if !event::poll(left.min(Duration::from_millis(100)))? {
+                continue;
+            }
+        }
+        let key = match event::read()? {
+            Event::Key(key) if key.kind != KeyEventKind::Release => key,
+            _ => continue,
+        };
+        if counting {
+            // the first key stops the countdown
+            counting = false;
+            queue!(out, MoveToColumn(0), Clear(ClearType::CurrentLine))?;
+            write!(out, "{}", prompt)?;
+        }
+        match plain_key(&mut text, key, false) {
+            Action::Submit => break,
+            Action::Stop(interrupt) => {
+                execute!(out, crossterm::style::Print("\r\n"))?;
+                return Ok(Err(interrupt));
+            }
+            Action::Typed(c) => write!(out, "{}", c)?,
+            Action::Erased => write!(out, "\u{8} \u{8}")?,
+            _ => {}
+        }
+        out.flush()?;
+    }
+    execute!(out, crossterm::style::Print("\r\n"))?;
+    Ok(Ok(text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(line: &mut Line, keys: &[KeyCode], history: Option<&History>) -> Action {
+        keys.iter()
+            .map(|&code| line.key(KeyEvent::new(code, KeyModifiers::NONE), history))
+            .last()
+            .unwrap()
+    }
+    fn ctrl(c: char) -> KeyEvent {
+        KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL)
+    }
+    fn text(line: &Line) -> String {
+        line.chars.iter().collect()
+    }
+
+    #[test]
+    fn edits_at_the_cursor() {
+        let mut line = Line::new(2, "héllo");
+        let keys = [KeyCode::Left, KeyCode::Left, KeyCode::Backspace, KeyCode::Char('L'), KeyCode::Delete];
+        assert_eq!(press(&mut line, &keys, None), Action::Edited);
+        assert_eq!((text(&line).as_str(), line.cursor), ("héLo", 3));
+        assert_eq!(line.key(ctrl('a'), None), Action::Edited);
+        assert_eq!(press(&mut line, &[KeyCode::Backspace], None), Action::Ignored);
+        press(&mut line, &[KeyCode::Char('>'), KeyCode::End, KeyCode::Right, KeyCode::Char('!')], None);
+        assert_eq!((text(&line).as_str(), line.cursor), (">héLo!", 6));
+        press(&mut line, &[KeyCode::Home, KeyCode::Left, KeyCode::Delete], None);
+        assert_eq!(line.key(ctrl('e'), None), Action::Edited);
+        assert_eq!((text(&line).as_str(), line.cursor), ("héLo!", 5));
+        assert_eq!(press(&mut line, &[KeyCode::Delete], None), Action::Ignored);
+        assert_eq!(press(&mut line, &[KeyCode::Tab], None), Action::Complete);
+        assert_eq!(press(&mut line, &[KeyCode::Enter], None), Action::Submit);
+    }
+
+    #[test]
+    fn recalls_history_and_the_draft() {
+        let mut history = History::new(10);
+        history.push("first");
+        history.push("second");
+        let mut line = Line::new(0, "dra");
+        press(&mut line, &[KeyCode::Char('f'), KeyCode::Up], Some(&history));
+        assert_eq!(text(&line), "second");
+        press(&mut line, &[KeyCode::Up, KeyCode::Up], Some(&history));
+        assert_eq!((text(&line).as_str(), line.cursor), ("first", 5));
+        press(&mut line, &[KeyCode::Backspace, KeyCode::Down], Some(&history));
+        assert_eq!(text(&line), "second");
+        press(&mut line, &[KeyCode::Down, KeyCode::Down], Some(&history));
+        assert_eq!((text(&line).as_str(), line.recalled), ("draf", None));
+        press(&mut line, &[KeyCode::Up], None);
+        assert_eq!(text(&line), "draf");
+    }
+
+    #[test]
+    fn ctrl_d_stops_only_an_empty_line() {
+        let mut line = Line::new(0, "x");
+        assert_eq!(line.key(ctrl('d'), None), Action::Ignored);
+        assert_eq!(line.key(ctrl('x'), None), Action::Ignored);
+        press(&mut line, &[KeyCode::Backspace], None);
+        assert_eq!(line.key(ctrl('d'), None), Action::Stop(Interrupt::Eof));
+        line.set("typed");
+        assert_eq!(line.key(ctrl('c'), None), Action::Stop(Interrupt::Interrupted));
+    }
+
+    #[test]
+    fn masked_keys_change_the_hidden_text() {
+        let mut text = String::new();
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+        assert_eq!(plain_key(&mut text, key(KeyCode::Backspace), true), Action::Ignored);
+        assert_eq!(plain_key(&mut text, ctrl('d'), true), Action::Stop(Interrupt::Eof));
+        assert_eq!(plain_key(&mut text, key(KeyCode::Char('p')), true), Action::Typed('p'));
+        assert_eq!(plain_key(&mut text, key(KeyCode::Char('w')), true), Action::Typed('w'));
+        assert_eq!(plain_key(&mut text, ctrl('d'), true), Action::Ignored);
+        assert_eq!(plain_key(&mut text, key(KeyCode::Left), true), Action::Ignored);
+        assert_eq!(plain_key(&mut text, key(KeyCode::Backspace), true), Action::Erased);
+        assert_eq!(text, "p");
+        assert_eq!(plain_key(&mut text, key(KeyCode::Enter), true), Action::Submit);
+        assert_eq!(plain_key(&mut text, ctrl('c'), true), Action::Stop(Interrupt::Interrupted));
+    }
+
+    #[test]
+    fn ctrl_d_doesnt_end_a_timed_line() {
+        let mut text = String::new();
+        assert_eq!(plain_key(&mut text, ctrl('d'), false), Action::Ignored);
+        let key = KeyEvent::new(KeyCode::Char('y'), KeyModifiers::SHIFT);
+        assert_eq!(plain_key(&mut text, key, false), Action::Typed('y'));
+        assert_eq!(plain_key(&mut text, ctrl('c'), false), Action::Stop(Interrupt::Interrupted));
+        assert_eq!(text, "y");
+    }
+
+    #[test]
+    fn completes_the_text_before_the_cursor() {
+        let completer: Completer = Box::new(|before: &str| {
+            let all = ["serve", "server", "service", "stop"];
+            all.iter().filter(|c| c.starts_with(before)).map(|c| c.to_string()).collect()
+        });
+        let mut out = Vec::new();
+        let mut line = Line::new(0, "se --port");
+        line.cursor = 2;
+        line.complete(&completer, &mut out).unwrap();
+        assert_eq!((text(&line).as_str(), line.cursor), ("serv --port", 4));
+        // the prefix doesn't grow, so the candidates are listed below
+        line.complete(&completer, &mut out).unwrap();
+        assert_eq!(text(&line), "serv --port");
+        assert!(String::from_utf8_lossy(&out).starts_with("\r\nserve  server  service"));
+        out.clear();
+        line.set("st");
+        line.complete(&completer, &mut out).unwrap();
+        assert_eq!((text(&line).as_str(), line.cursor), ("stop", 4));
+        line.set("x");
+        line.complete(&completer, &mut out).unwrap();
+        assert_eq!(text(&line), "x");
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn common_prefix_of_chars() {
+        assert_eq!(common_prefix("héllo", "hérons"), "hé");
+        assert_eq!(common_prefix("hé", "hè"), "h");
+        assert_eq!(common_prefix("ab", "abc"), "ab");
+        assert_eq!(common_prefix("abc", ""), "");
+    }
+}
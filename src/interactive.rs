@@ -0,0 +1,249 @@
+//!
+//! Helpers for interactive programs
+//!
+//! ``` rust
+//!     use rcin::RInStream;
+//!     use rcin::interactive::{form, Field};
+//!
+//!     let mut reader = RInStream::from_source(Box::new(&b"db\n80\n8000\n\n"[..]));
+//!     let values = form()
+//!         .field("host", Field::<String>::new())
+//!         .field("port", Field::new().validate(|p: &u16| *p > 1024))
+//!         .field("workers", Field::new().with_default(4usize))
+//!         .run_with(&mut reader, &mut Vec::new())
+//!         .unwrap();
+//!     assert_eq!(values.get::<String>("host").unwrap(), "db");
+//!     assert_eq!(values.get::<u16>("port"), Some(&8000));
+//!     assert_eq!(values.get::<usize>("workers"), Some(&4));
+//! ```
+//!
+
+use crate::{RCin, RcinRead, ReadError};
+use std::any::Any;
+use std::collections::{HashMap, VecDeque};
+use std::fmt::{self, Display};
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+// answer of a form field, the type is erased
+type Answer = Box<dyn Any + Send>;
+// None if the answer is invalid
+type ParseAnswer = Box<dyn Fn(&str) -> Option<Answer> + Send>;
+type Validator<T> = Box<dyn Fn(&T) -> bool + Send>;
+
+/// Field of a [`Form`] with an optional default and validator
+pub struct Field<T> {
+    default: Option<T>,
+    validator: Option<Validator<T>>,
+}
+
+impl<T> Field<T> {
+    /// Field without a default that accepts every parsed value
+    pub fn new() -> Self {
+        Field { default: None, validator: None }
+    }
+    /// Value taken on an empty answer
+    pub fn with_default(mut self, value: T) -> Self {
+        self.default = Some(value);
+        self
+    }
+    /// Reject parsed values that fail f, the field is asked again
+    pub fn validate(mut self, f: impl Fn(&T) -> bool + Send + 'static) -> Self {
+        self.validator = Some(Box::new(f));
+        self
+    }
+}
+
+impl<T> Default for Field<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+struct Entry {
+    name: String,
+    default: Option<String>,
+    parse: ParseAnswer,
+}
+
+/// Fields asked one after another, created by [`form`]
+#[derive(Default)]
+pub struct Form {
+    entries: Vec<Entry>,
+}
+
+/// Start a form without fields
+pub fn form() -> Form {
+    Form::default()
+}
+
+impl Form {
+    /// Add a field asked with its name as label
+    pub fn field<T>(mut self, name: &str, field: Field<T>) -> Self
+    where
+        T: FromStr + Display + Clone + Send + 'static,
+    {
+        let Field { default, validator } = field;
+        let label = default.as_ref().map(T::to_string);
+        let parse = move |answer: &str| {
+            let value = match (answer.is_empty(), &default) {
+                (true, Some(default)) => default.clone(),
+                _ => answer.parse::<T>().ok()?,
+            };
+            match &validator {
+                Some(valid) if !valid(&value) => None,
+                _ => Some(Box::new(value) as Answer),
+            }
+        };
+        self.entries.push(Entry {
+            name: name.to_string(),
+            default: label,
+            parse: Box::new(parse),
+        });
+        self
+    }
+    /// Ask all fields on stdout and read answers from rin
+    pub fn run(&self, rin: &RCin) -> Result<FormValues, ReadError> {
+        let mut lock = rin.lock();
+        self.run_with(&mut lock, &mut io::stdout())
+    }
+    /// Ask all fields on out, an invalid answer asks the field again until the reader ends
+    pub fn run_with(&self, reader: &mut impl RcinRead, out: &mut dyn Write) -> Result<FormValues, ReadError> {
+        let mut values = HashMap::new();
+        for entry in &self.entries {
+            let value = loop {
+                let _ = match &entry.default {
+                    Some(default) => write!(out, "{} [{}]: ", entry.name, default),
+                    None => write!(out, "{}: ", entry.name),
+                };
+                let _ = out.flush();
+                let line = reader.read_line_checked()?;
+                match (entry.parse)(line.trim()) {
+                    Some(value) => break value,
+                    None => {
+                        let _ = writeln!(out, "invalid {}", entry.name);
+                    }
+                }
+            };
+            values.insert(entry.name.clone(), value);
+        }
+        Ok(FormValues { values })
+    }
+}
+
+/// Answers of a [`Form`] by field name
+pub struct FormValues {
+    values: HashMap<String, Answer>,
+}
+
+impl FormValues {
+    /// Answer of a field, None if there is no such field or it has another type
+    pub fn get<T: 'static>(&self, name: &str) -> Option<&T> {
+        self.values.get(name)?.downcast_ref()
+    }
+    /// Take the answer of a field out
+    pub fn take<T: 'static>(&mut self, name: &str) -> Option<T> {
+        let value = self.values.remove(name)?;
+        match value.downcast() {
+            Ok(value) => Some(*value),
+            Err(value) => {
+                self.values.insert(name.to_string(), value);
+                None
+            }
+        }
+    }
+}
+
+/// Lines read while it's set on a stream, the oldest ones are dropped above the cap
+///
+/// Empty lines and repeats of the last line are not recorded
+///
+/// ``` rust
+///     use rcin::RInStream;
+///     use rcin::interactive::History;
+///
+///     let mut reader = RInStream::from_source(Box::new(&b"ls\nls\n\ncd /\n"[..]));
+///     reader.set_history(Some(History::new(10)));
+///     while reader.read_line().is_some() {}
+///     let history = reader.history().unwrap();
+///     assert_eq!(history.iter().collect::<Vec<_>>(), ["ls", "cd /"]);
+///     assert_eq!(history.last(), Some("cd /"));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct History {
+    lines: VecDeque<String>,
+    cap: usize,
+}
+
+impl History {
+    /// Empty history keeping up to cap lines
+    pub fn new(cap: usize) -> Self {
+        History { lines: VecDeque::new(), cap }
+    }
+    /// Load lines saved by `save`, keeping the newest cap of them
+    pub fn load(path: impl AsRef<Path>, cap: usize) -> io::Result<Self> {
+        let mut history = History::new(cap);
+        for line in fs::read_to_string(path)?.lines() {
+            history.push(line);
+        }
+        Ok(history)
+    }
+    /// Write the lines to a file, one per line
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut file = io::BufWriter::new(fs::File::create(path)?);
+        for line in &self.lines {
+            writeln!(file, "{}", line)?;
+        }
+        file.flush()
+    }
+    /// Record a line
+    pub fn push(&mut self, line: &str) {
+        if line.is_empty() || self.last() == Some(line) || self.cap == 0 {
+            return;
+        }
+        if self.lines.len() == self.cap {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(line.to_string());
+    }
+    /// Most recent line
+    pub fn last(&self) -> Option<&str> {
+        self.lines.back().map(String::as_str)
+    }
+    /// Lines from the oldest to the newest
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &str> {
+        self.lines.iter().map(String::as_str)
+    }
+    /// Number of recorded lines
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+    /// Whether no lines are recorded
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+    /// Drop all recorded lines
+    pub fn clear(&mut self) {
+        self.lines.clear();
+    }
+}
+
+/// Why `rin.read_line_interruptible` returned no line
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Interrupt {
+    /// Ctrl-C was pressed
+    Interrupted,
+    /// Ctrl-D was pressed on an empty line or the input ended
+    Eof,
+}
+impl fmt::Display for Interrupt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Interrupt::Interrupted => write!(f, "interrupted"),
+            Interrupt::Eof => write!(f, "end of input"),
+        }
+    }
+}
+impl std::error::Error for Interrupt {}
@@ -53,28 +53,491 @@
 //! to speed up the parsing process and use the builtin utf8 parser. This also means that such streams won't be
 //! able to read the data char by char efficiently.
 //!
+//! Streams can already be limited to ascii whitespace with `WhitespaceMode::AsciiOnly`.
+//!
+//! On Windows stdin attached to a console, and the console opened by `enable_tty_fallback`, are
+//! read as utf16 with `ReadConsoleW` and converted to utf8, independent of the console code page.
+//! Ctrl-Z at the start of a line ends the input. Piped or redirected stdin is read as raw bytes
+//! and has to be utf8 encoded. Chars outside the basic plane, which the console sends as utf16
+//! surrogate pairs, arrive as 4 byte utf8 sequences:
+//!
+//! ``` rust
+//!     use rcin::RInStream;
+//!
+//!     let typed: String = String::from_utf16(&[0x00e9, 0x20, 0xd83d, 0xde00, 0x20, 0x65e5, 0x672c]).unwrap();
+//!     let mut reader = RInStream::from_source(Box::new(std::io::Cursor::new(typed)));
+//!     assert_eq!(reader.read::<String>(), Some("é".to_string()));
+//!     assert_eq!(reader.read::<char>(), Some('😀'));
+//!     assert_eq!(reader.read::<String>(), Some("日本".to_string()));
+//! ```
+//!
+//! ## WebAssembly
+//!
+//! On wasm targets (wasm32-wasi, wasm32-unknown-unknown) everything that needs threads, like
+//! line channels and `MultiStream`, is left out. The global stream reads wasi stdin, in a browser
+//! it can be fed with `rin.set_source`.
+//!
+//! ## no_std
+//!
+//! Without the default `std` feature the crate only needs `alloc`. Streams are created with
+//! `RInStream::from_byte_source` over anything that implements `ByteSource`, like a UART driver.
+//! The global stdin stream, files, seeking and threads are only available with `std`.
+//!
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+mod macros;
+
+pub mod config;
+#[cfg(all(feature = "std", windows))]
+mod console;
+pub mod cp;
+#[cfg(feature = "editor")]
+mod editor;
+#[cfg(feature = "std")]
+pub mod interactive;
+pub mod manip;
+#[cfg(all(feature = "std", not(target_family = "wasm")))]
+mod multi;
+#[cfg(feature = "std")]
+pub mod par;
+#[cfg(feature = "std")]
+mod registry;
+#[cfg(all(feature = "std", not(target_family = "wasm")))]
+mod scan;
+#[cfg(feature = "std")]
+mod shared;
+#[cfg(all(feature = "io-uring", target_os = "linux"))]
+mod uring;
+#[cfg(test)]
+mod tests;
+
+#[cfg(all(feature = "std", not(target_family = "wasm")))]
+pub use multi::MultiStream;
+#[cfg(feature = "std")]
+pub use registry::{register, stream};
+#[cfg(all(feature = "std", not(target_family = "wasm")))]
+pub use scan::{analyze, FileStats};
+#[cfg(feature = "std")]
+pub use shared::SharedRIn;
 
-use lazy_static::lazy_static;
-use std::cell::{RefCell, RefMut};
-use std::io::{stdin, Read};
-use std::str::FromStr;
-use std::sync::Mutex;
+#[cfg(not(feature = "std"))]
+use alloc::{boxed::Box, format, string::String, vec, vec::Vec};
+use alloc::borrow::Cow;
+use alloc::collections::{BTreeMap, BTreeSet, VecDeque};
+use core::any::{Any, TypeId};
+use core::convert::TryFrom;
+use core::fmt;
+use core::ops::RangeBounds;
+use core::str::FromStr;
+#[cfg(feature = "std")]
+use std::collections::{HashMap, HashSet};
+#[cfg(feature = "std")]
 use std::fs::File;
+#[cfg(feature = "std")]
+use std::hash::Hash;
+#[cfg(feature = "std")]
+use std::io::{self, stdin, Read, Seek, SeekFrom};
+#[cfg(all(feature = "std", not(target_family = "wasm")))]
+use std::sync::mpsc::{self, Receiver};
+#[cfg(feature = "std")]
+use std::cell::Cell;
+#[cfg(feature = "std")]
+use std::sync::{Mutex, MutexGuard, OnceLock, TryLockError};
+#[cfg(all(feature = "std", not(target_family = "wasm")))]
+use std::thread;
 
+#[cfg(feature = "std")]
 const DEFAULT_BUF_SIZE: usize = 8_000; //8 KB like BufReader
+const MAX_LOOKAHEAD: usize = 16; // chars returned by lookahead
+
+/// Error of a [`ByteSource`], the stream treats it like the end of input
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SourceError;
+
+/// Source of bytes for a stream, the only thing needed to read without std
+pub trait ByteSource: Send {
+    /// Fill buf with the next bytes and return how many were written, 0 means end of input
+    fn read_bytes(&mut self, buf: &mut [u8]) -> Result<usize, SourceError>;
+    /// Reposition the source, None if it isn't seekable
+    #[cfg(feature = "std")]
+    fn seek(&mut self, _pos: SeekFrom) -> Option<io::Result<u64>> {
+        None
+    }
+}
+impl ByteSource for &'static [u8] {
+    fn read_bytes(&mut self, buf: &mut [u8]) -> Result<usize, SourceError> {
+        let n = buf.len().min(self.len());
+        let (head, tail) = self.split_at(n);
+        buf[..n].copy_from_slice(head);
+        *self = tail;
+        Ok(n)
+    }
+}
+// stops after a number of bytes
+struct Limited {
+    inner: Box<dyn ByteSource>,
+    left: u64,
+}
+impl ByteSource for Limited {
+    fn read_bytes(&mut self, buf: &mut [u8]) -> Result<usize, SourceError> {
+        let max = buf.len().min(usize::try_from(self.left).unwrap_or(usize::MAX));
+        let n = self.inner.read_bytes(&mut buf[..max])?;
+        self.left -= n as u64;
+        Ok(n)
+    }
+}
+// transform of a chunk of bytes, used by map_bytes
+type ByteMap = Box<dyn FnMut(&mut Vec<u8>) + Send>;
+// every chunk read from inner goes through map before it's decoded
+struct Mapped {
+    inner: Box<dyn ByteSource>,
+    map: ByteMap,
+    chunk: Vec<u8>,
+    ptr: usize,
+}
+impl ByteSource for Mapped {
+    fn read_bytes(&mut self, buf: &mut [u8]) -> Result<usize, SourceError> {
+        // a chunk mapped to nothing isn't the end of the input
+        while self.ptr >= self.chunk.len() {
+            self.chunk.resize(buf.len().max(1), 0);
+            let n = self.inner.read_bytes(&mut self.chunk)?;
+            self.ptr = 0;
+            self.chunk.truncate(n);
+            if n == 0 {
+                return Ok(0);
+            }
+            (self.map)(&mut self.chunk);
+        }
+        let n = buf.len().min(self.chunk.len() - self.ptr);
+        buf[..n].copy_from_slice(&self.chunk[self.ptr..self.ptr + n]);
+        self.ptr += n;
+        Ok(n)
+    }
+}
+// drops ansi escape sequences, a sequence can span reads
+struct AnsiStripped {
+    inner: Box<dyn ByteSource>,
+    state: AnsiState,
+}
+#[derive(Copy, Clone)]
+enum AnsiState {
+    Text,
+    Escape,
+    Csi,         // ESC [ up to the final byte
+    Osc,         // ESC ] up to BEL or ESC \
+    OscEscape,
+}
+impl AnsiStripped {
+    // whether b is text and the state after it
+    fn step(state: AnsiState, b: u8) -> (bool, AnsiState) {
+        match (state, b) {
+            (AnsiState::Text, 0x1b) => (false, AnsiState::Escape),
+            (AnsiState::Text, _) => (true, AnsiState::Text),
+            (AnsiState::Escape, b'[') => (false, AnsiState::Csi),
+            (AnsiState::Escape, b']') => (false, AnsiState::Osc),
+            (AnsiState::Escape, _) => (false, AnsiState::Text),
+            (AnsiState::Csi, 0x20..=0x3f) => (false, AnsiState::Csi),
+            (AnsiState::Csi, _) => (false, AnsiState::Text),
+            (AnsiState::Osc | AnsiState::OscEscape, 0x07) => (false, AnsiState::Text),
+            (AnsiState::Osc | AnsiState::OscEscape, 0x1b) => (false, AnsiState::OscEscape),
+            (AnsiState::OscEscape, b'\\') => (false, AnsiState::Text),
+            (AnsiState::Osc | AnsiState::OscEscape, _) => (false, AnsiState::Osc),
+        }
+    }
+}
+impl ByteSource for AnsiStripped {
+    fn read_bytes(&mut self, buf: &mut [u8]) -> Result<usize, SourceError> {
+        loop {
+            let n = self.inner.read_bytes(buf)?;
+            let mut kept = 0;
+            for i in 0..n {
+                let (text, state) = Self::step(self.state, buf[i]);
+                self.state = state;
+                if text {
+                    buf[kept] = buf[i];
+                    kept += 1;
+                }
+            }
+            // a read of only escape codes isn't the end of the input
+            if kept > 0 || n == 0 {
+                return Ok(kept);
+            }
+        }
+    }
+}
+#[cfg(feature = "std")]
+struct Plain<R>(R);
+#[cfg(feature = "std")]
+impl<R: Read + Send> ByteSource for Plain<R> {
+    fn read_bytes(&mut self, buf: &mut [u8]) -> Result<usize, SourceError> {
+        self.0.read(buf).map_err(|_| SourceError)
+    }
+}
+#[cfg(feature = "std")]
+struct Seekable<R>(R);
+#[cfg(feature = "std")]
+impl<R: Read + Seek + Send> ByteSource for Seekable<R> {
+    fn read_bytes(&mut self, buf: &mut [u8]) -> Result<usize, SourceError> {
+        self.0.read(buf).map_err(|_| SourceError)
+    }
+    fn seek(&mut self, pos: SeekFrom) -> Option<io::Result<u64>> {
+        Some(self.0.seek(pos))
+    }
+}
+// lets std apis like LineIndex::build read a source
+#[cfg(feature = "std")]
+impl Read for dyn ByteSource {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.read_bytes(buf)
+            .map_err(|_| io::Error::other("source error"))
+    }
+}
+
+// utf16 or latin1 input converted to utf8, used by from_path_autodetect
+#[cfg(feature = "encoding")]
+struct Transcoded<R> {
+    inner: R,
+    encoding: Encoding,
+    carry: Vec<u8>, // bytes of an incomplete utf16 char
+    out: Vec<u8>,   // converted bytes handed out from start
+    start: usize,
+}
+#[cfg(feature = "encoding")]
+impl<R: Read> Transcoded<R> {
+    fn convert(&mut self, raw: &[u8], eof: bool) {
+        self.out.clear();
+        self.start = 0;
+        let big = match self.encoding {
+            Encoding::Utf8 => {
+                self.out.extend_from_slice(raw);
+                return;
+            }
+            Encoding::Latin1 => {
+                for &b in raw {
+                    push_utf8(&mut self.out, char::from(b));
+                }
+                return;
+            }
+            Encoding::Utf16Le => false,
+            Encoding::Utf16Be => true,
+        };
+        self.carry.extend_from_slice(raw);
+        let mut units: Vec<u16> = self
+            .carry
+            .chunks_exact(2)
+            .map(|pair| if big { u16::from_be_bytes([pair[0], pair[1]]) } else { u16::from_le_bytes([pair[0], pair[1]]) })
+            .collect();
+        // a surrogate pair can be split by the end of raw
+        let mut used = units.len() * 2;
+        if !eof && units.last().is_some_and(|u| (0xD800..0xDC00).contains(u)) {
+            units.pop();
+            used -= 2;
+        }
+        for c in char::decode_utf16(units) {
+            push_utf8(&mut self.out, c.unwrap_or(char::REPLACEMENT_CHARACTER));
+        }
+        if eof && used < self.carry.len() {
+            push_utf8(&mut self.out, char::REPLACEMENT_CHARACTER);
+            used = self.carry.len();
+        }
+        self.carry.drain(..used);
+    }
+}
+#[cfg(feature = "encoding")]
+fn push_utf8(out: &mut Vec<u8>, c: char) {
+    out.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+}
+#[cfg(feature = "encoding")]
+impl<R: Read + Send> ByteSource for Transcoded<R> {
+    fn read_bytes(&mut self, buf: &mut [u8]) -> Result<usize, SourceError> {
+        let mut raw = [0; 4096];
+        while self.start == self.out.len() {
+            let n = self.inner.read(&mut raw).map_err(|_| SourceError)?;
+            self.convert(&raw[..n], n == 0);
+            if n == 0 && self.out.is_empty() {
+                return Ok(0);
+            }
+        }
+        let n = buf.len().min(self.out.len() - self.start);
+        buf[..n].copy_from_slice(&self.out[self.start..self.start + n]);
+        self.start += n;
+        Ok(n)
+    }
+}
+
+// switches to the controlling terminal once the inner source ends
+#[cfg(feature = "std")]
+struct TtyFallback {
+    inner: Box<dyn ByteSource>,
+    switched: bool,
+}
+#[cfg(all(feature = "std", not(windows)))]
+const TTY_PATH: &str = "/dev/tty";
+#[cfg(feature = "std")]
+impl ByteSource for TtyFallback {
+    fn read_bytes(&mut self, buf: &mut [u8]) -> Result<usize, SourceError> {
+        let n = self.inner.read_bytes(buf)?;
+        if n == 0 && !self.switched {
+            self.switched = true;
+            #[cfg(windows)]
+            let tty = console::Console::conin().map_err(|_| SourceError)?;
+            #[cfg(not(windows))]
+            let tty = File::open(TTY_PATH).map_err(|_| SourceError)?;
+            self.inner = Box::new(Plain(tty));
+            return self.inner.read_bytes(buf);
+        }
+        Ok(n)
+    }
+}
 
 /*
     Internal buffered stream that reads char by char using an utf8 decoder
 */
 struct Stream {
-    source: Box<dyn Read + Send>,
+    source: Box<dyn ByteSource>,
     buf: Vec<u8>,
     ptr: usize,
     limit: usize,
     error: bool, // true when the source returns an error
+    io_error: bool, // the error wasn't the end of input
+    header: Option<(char, Vec<String>)>, // csv header with its delimiter
+    consumed: usize, // bytes taken from buf since the start
+    pos: Position,   // position of the next char
+    pushback: Vec<(char, Position)>, // chars to return before the source, last is next
+    journal: Vec<(char, Position)>,  // chars consumed since the oldest mark
+    marks: Vec<(usize, Vec<Budget>, u64)>, // journal positions of active marks, budgets and tokens read at them
+    whitespace: WhitespaceMode,
+    skipws: bool, // skip whitespace around tokens
+    continuation: bool, // join lines ending in a backslash
+    strip_cr: bool,     // read \r\n as \n
+    endings: (usize, usize, bool), // lf and crlf line breaks decoded so far, last char was \r
+    stats: Stats,
+    token_start: Position, // position of the last token
+    token_end: Position,   // position after the last char of the last token
+    total_size: Option<u64>, // size of the source if known
+    max_token_len: Option<usize>, // in chars
+    max_line_len: Option<usize>,  // in chars
+    progress: Option<ProgressHook>,
+    adaptive: Option<Adaptive>,
+    budgets: Vec<Budget>, // limits of active take_tokens/take_lines views
+    peeked: String,       // last result of peek_token/peek_line
+    window: Vec<char>,    // last result of lookahead
+    parsers: BTreeMap<TypeId, Box<dyn Any + Send>>, // Parser<T> by the TypeId of T
+    scratch: String,                      // reused token buffer
+    symbols: Vec<String>,                 // interned tokens by Symbol
+    symbol_ids: BTreeMap<String, Symbol>,
+    last_failure: Option<ReadFailure>,
+    policy: ParsePolicy,
+    error_handler: Option<ErrorHandler>,
+    #[cfg(feature = "std")]
+    trace: bool, // print every token and line to stderr
+    #[cfg(feature = "unicode-normalization")]
+    nfc: bool, // normalize tokens and lines to NFC
+    fold_case: bool, // lowercase tokens
+    defaults: BTreeMap<TypeId, Box<dyn Any + Send>>, // fn() -> T by the TypeId of T for ParsePolicy::UseDefault
+    #[cfg(feature = "std")]
+    line_index: Option<LineIndex>,
+    #[cfg(feature = "std")]
+    history: Option<interactive::History>, // lines read by read_line
+    #[cfg(feature = "editor")]
+    line_editor: bool, // rin.read_line edits lines on a terminal
+    #[cfg(feature = "editor")]
+    on_stdin: bool, // the source is stdin of the process
+    #[cfg(feature = "editor")]
+    completer: Option<editor::Completer>,
+    #[cfg(feature = "editor")]
+    draft: String, // pasted text of an unfinished line, the editor starts with it
+    #[cfg(feature = "digest")]
+    digest: Option<(Box<dyn digest::DynDigest + Send>, usize)>, // hasher and hashed part of buf
+    #[cfg(all(feature = "std", not(target_family = "wasm")))]
+    wait_handle: Option<multi::WaitHandle>, // waited on by MultiStream, None if reads never block
+}
+// custom parse function of a type, consulted before FromStr
+type Parser<T> = Box<dyn Fn(&str) -> Option<T> + Send>;
+// called on every failed read
+type ErrorHandler = Box<dyn FnMut(&ReadFailure) + Send>;
+
+#[derive(Clone)]
+enum Budget {
+    Tokens(usize),
+    Lines(usize),
+}
+impl Budget {
+    fn left(&self) -> usize {
+        match self {
+            Budget::Tokens(n) | Budget::Lines(n) => *n,
+        }
+    }
+}
+// buffer size between min and max following how much the source returns
+struct Adaptive {
+    min: usize,
+    max: usize,
+    full: u32,  // consecutive refills that filled the buffer
+    small: u32, // consecutive refills that used less than a quarter of it
+}
+impl Adaptive {
+    const GROW_AFTER: u32 = 4;
+    const SHRINK_AFTER: u32 = 16;
+    // size for the next refill, kept bytes have to fit
+    fn next_size(&mut self, len: usize, kept: usize) -> usize {
+        if self.full >= Self::GROW_AFTER {
+            self.full = 0;
+            (len * 2).min(self.max)
+        } else if self.small >= Self::SHRINK_AFTER {
+            self.small = 0;
+            (len / 2).max(self.min).max(kept)
+        } else {
+            len
+        }
+    }
+    fn record(&mut self, read: usize, space: usize) {
+        if read == space {
+            self.full += 1;
+            self.small = 0;
+        } else if read < space / 4 {
+            self.small += 1;
+            self.full = 0;
+        } else {
+            self.full = 0;
+            self.small = 0;
+        }
+    }
+}
+// integer token parsed from the byte buffer by scan_int
+struct ScannedInt {
+    value: u64, // absolute value, wrapped around on overflow
+    sign: Option<u8>,
+    overflow: bool,
+    digits: usize,
+    long: String, // all digits once there are more than value can hold exactly
+    ended: bool,  // whitespace or the end of input came after the digits
+}
+impl ScannedInt {
+    const EXACT_DIGITS: usize = 19;
+}
+impl fmt::Display for ScannedInt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(sign) = self.sign {
+            write!(f, "{}", sign as char)?;
+        }
+        if self.digits > Self::EXACT_DIGITS {
+            f.write_str(&self.long)
+        } else {
+            write!(f, "{:01$}", self.value, self.digits)
+        }
+    }
+}
+struct ProgressHook {
+    interval: usize,
+    next: usize,
+    callback: Box<dyn FnMut(Progress) + Send>,
 }
 impl Stream {
-    fn new(source: Box<dyn Read + Send>, buf_size: usize) -> Self {
+    fn new(source: Box<dyn ByteSource>, buf_size: usize) -> Self {
         let vc = vec![0; buf_size];
         Stream {
             source,
@@ -82,24 +545,291 @@ impl Stream {
             ptr: 0,
             limit: 0,
             error: false,
+            io_error: false,
+            header: None,
+            consumed: 0,
+            pos: Position::start(),
+            pushback: Vec::new(),
+            journal: Vec::new(),
+            marks: Vec::new(),
+            whitespace: WhitespaceMode::default(),
+            skipws: true,
+            continuation: false,
+            strip_cr: false,
+            endings: (0, 0, false),
+            stats: Stats::default(),
+            token_start: Position::start(),
+            token_end: Position::start(),
+            total_size: None,
+            max_token_len: None,
+            max_line_len: None,
+            progress: None,
+            budgets: Vec::new(),
+            peeked: String::new(),
+            window: Vec::new(),
+            parsers: BTreeMap::new(),
+            scratch: String::new(),
+            symbols: Vec::new(),
+            symbol_ids: BTreeMap::new(),
+            last_failure: None,
+            policy: ParsePolicy::default(),
+            error_handler: None,
+            #[cfg(feature = "std")]
+            trace: std::env::var_os("RCIN_TRACE").is_some_and(|v| !v.is_empty() && v != "0"),
+            #[cfg(feature = "unicode-normalization")]
+            nfc: false,
+            fold_case: false,
+            defaults: BTreeMap::new(),
+            #[cfg(feature = "std")]
+            line_index: None,
+            adaptive: None,
+            #[cfg(feature = "std")]
+            history: None,
+            #[cfg(feature = "editor")]
+            line_editor: false,
+            #[cfg(feature = "editor")]
+            on_stdin: false,
+            #[cfg(feature = "editor")]
+            completer: None,
+            #[cfg(feature = "editor")]
+            draft: String::new(),
+            #[cfg(feature = "digest")]
+            digest: None,
+            #[cfg(all(feature = "std", not(target_family = "wasm")))]
+            wait_handle: None,
+        }
+    }
+    // switch to another source keeping the buffer and configuration
+    #[cfg(feature = "std")]
+    fn reset(&mut self, source: Box<dyn ByteSource>) {
+        #[cfg(feature = "digest")]
+        self.update_digest();
+        self.source = source;
+        #[cfg(all(feature = "std", not(target_family = "wasm")))]
+        {
+            self.wait_handle = None;
+        }
+        self.ptr = 0;
+        self.limit = 0;
+        self.error = false;
+        self.io_error = false;
+        self.header = None;
+        self.consumed = 0;
+        self.pos = Position::start();
+        self.pushback.clear();
+        self.journal.clear();
+        self.marks.clear();
+        self.stats = Stats::default();
+        self.endings = (0, 0, false);
+        self.token_start = Position::start();
+        self.token_end = Position::start();
+        self.total_size = None;
+        #[cfg(feature = "std")]
+        {
+            self.line_index = None;
         }
+        #[cfg(feature = "digest")]
+        if let Some((_, hashed)) = self.digest.as_mut() {
+            *hashed = 0;
+        }
+    }
+    #[cfg(feature = "std")]
+    // reposition a seekable source, the position is relative to the consumed input
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let pos = match pos {
+            SeekFrom::Current(delta) => {
+                let current = self.pos.offset as i64;
+                let target = current.checked_add(delta).filter(|&t| t >= 0).ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::InvalidInput, "invalid seek to a negative position")
+                })?;
+                SeekFrom::Start(target as u64)
+            }
+            pos => pos,
+        };
+        let res = self.source.seek(pos).unwrap_or_else(|| {
+            Err(io::Error::new(io::ErrorKind::Unsupported, "source is not seekable"))
+        })?;
+        #[cfg(feature = "digest")]
+        self.update_digest();
+        self.ptr = 0;
+        #[cfg(feature = "digest")]
+        if let Some((_, hashed)) = self.digest.as_mut() {
+            *hashed = 0;
+        }
+        self.limit = 0;
+        self.error = false;
+        self.io_error = false;
+        self.consumed = res as usize;
+        self.pos = self.position_at(res)?;
+        self.pushback.clear();
+        self.journal.clear();
+        self.marks.clear();
+        Ok(res)
+    }
+    // count the position of a target offset from the closest known position before it,
+    // the source is left at the target
+    #[cfg(feature = "std")]
+    fn position_at(&mut self, target: u64) -> io::Result<Position> {
+        let mut known = Position::start();
+        if let Some((line, offset)) = self.line_index.as_ref().map(|index| index.line_at(target)) {
+            known = Position {
+                offset: offset as usize,
+                line,
+                column: 1,
+            };
+        }
+        if (known.offset..=target as usize).contains(&self.pos.offset) {
+            known = self.pos;
+        }
+        if known.offset as u64 == target {
+            return Ok(known);
+        }
+        self.source.seek(SeekFrom::Start(known.offset as u64)).unwrap_or_else(|| {
+            Err(io::Error::new(io::ErrorKind::Unsupported, "source is not seekable"))
+        })?;
+        let mut pos = known;
+        while pos.offset < target as usize {
+            let want = self.buf.len().min(target as usize - pos.offset);
+            let n = match self.source.read_bytes(&mut self.buf[..want]) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => n,
+            };
+            for &b in &self.buf[..n] {
+                // continuation bytes don't start a char
+                match b {
+                    b'\n' => {
+                        pos.line += 1;
+                        pos.column = 1;
+                    }
+                    b if b & 0xC0 == 0x80 => (),
+                    _ => pos.column += 1,
+                }
+            }
+            pos.offset += n;
+        }
+        if pos.offset != target as usize {
+            // the source ended before the target
+            self.source.seek(SeekFrom::Start(target)).unwrap_or(Ok(target))?;
+            pos.offset = target as usize;
+        }
+        Ok(pos)
+    }
+    #[cfg(feature = "std")]
+    fn build_line_index(&mut self) -> io::Result<()> {
+        let current = self.pos.offset as u64;
+        self.seek(SeekFrom::Start(0))?;
+        // the index is in place for the seek back
+        match LineIndex::build(&mut *self.source) {
+            Ok(index) => self.line_index = Some(index),
+            Err(e) => {
+                self.seek(SeekFrom::Start(current))?;
+                return Err(e);
+            }
+        }
+        self.seek(SeekFrom::Start(current))?;
+        Ok(())
+    }
+    #[cfg(feature = "std")]
+    fn goto_line(&mut self, line: usize) -> io::Result<()> {
+        let offset = self
+            .line_index
+            .as_ref()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no line index"))?
+            .offset(line)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "line out of range"))?;
+        self.seek(SeekFrom::Start(offset))?;
+        Ok(())
     }
+    // move the unconsumed bytes to the front and append new ones after them,
+    // the end of input or an error only counts once no bytes are left
     fn refill(&mut self) {
-        let res = self.source.read(&mut self.buf);
+        #[cfg(feature = "digest")]
+        self.update_digest();
+        let kept = self.limit - self.ptr;
+        self.buf.copy_within(self.ptr..self.limit, 0);
         self.ptr = 0;
+        self.limit = kept;
+        #[cfg(feature = "digest")]
+        if let Some((_, hashed)) = self.digest.as_mut() {
+            *hashed = 0;
+        }
+        if let Some(adaptive) = &mut self.adaptive {
+            let size = adaptive.next_size(self.buf.len(), kept);
+            if size != self.buf.len() {
+                self.buf.resize(size, 0);
+                self.buf.shrink_to_fit();
+            }
+        }
+        if kept == self.buf.len() {
+            return;
+        }
+        #[cfg(feature = "tracing")]
+        let started = std::time::Instant::now();
+        let res = self.source.read_bytes(&mut self.buf[kept..]);
+        if let (Some(adaptive), Ok(n)) = (&mut self.adaptive, &res) {
+            adaptive.record(*n, self.buf.len() - kept);
+        }
+        #[cfg(feature = "tracing")]
+        tracing::trace!(elapsed = ?started.elapsed(), ok = res.is_ok(), "refill");
         match res {
             Ok(0) => {
-                self.error = true;
+                self.error = kept == 0;
+                self.io_error = false;
             }
             Ok(n) => {
-                self.limit = n;
+                self.limit += n;
                 self.error = false;
+                self.io_error = false;
+                self.stats.refills += 1;
+                self.stats.bytes_read += n as u64;
+                self.report_progress();
             }
             Err(_e) => {
-                self.error = true;
+                self.error = kept == 0;
+                self.io_error = kept == 0;
+            }
+        }
+    }
+    fn set_adaptive_buffer(&mut self, max: Option<usize>) {
+        let len = self.buf.len();
+        self.adaptive = max.map(|max| Adaptive {
+            min: len,
+            max: max.max(len),
+            full: 0,
+            small: 0,
+        });
+    }
+    // hash bytes consumed since the last update
+    #[cfg(feature = "digest")]
+    fn update_digest(&mut self) {
+        if let Some((hasher, hashed)) = self.digest.as_mut() {
+            hasher.update(&self.buf[*hashed..self.ptr]);
+            *hashed = self.ptr;
+        }
+    }
+    fn report_progress(&mut self) {
+        let consumed = self.consumed;
+        let total = self.total_size;
+        if let Some(hook) = self.progress.as_mut() {
+            if consumed >= hook.next {
+                (hook.callback)(Progress {
+                    consumed: consumed as u64,
+                    total,
+                });
+                hook.next = consumed + hook.interval;
             }
         }
     }
+    fn peek_byte(&mut self) -> Option<u8> {
+        if self.ptr >= self.limit {
+            self.refill();
+        }
+        if self.error {
+            None
+        } else {
+            Some(self.buf[self.ptr])
+        }
+    }
     fn pop_byte(&mut self) -> Option<u8> {
         // no bytes left in buffer
         if self.ptr >= self.limit {
@@ -109,11 +839,242 @@ impl Stream {
             None
         } else {
             self.ptr += 1;
+            self.consumed += 1;
             Some(self.buf[self.ptr - 1])
         }
     }
-    // decoder tested on https://onlineutf8tools.com/convert-utf8-to-bytes
+    // None at the end of input and for skipped invalid bytes, valid() tells them apart
     fn pop_char(&mut self) -> Option<char> {
+        self.next_char_result().ok().flatten()
+    }
+    // chars are available without reading the source
+    fn char_buffered(&self) -> bool {
+        !self.pushback.is_empty() || self.ptr < self.limit
+    }
+    // decode one key, an escape is only part of a sequence if the rest is already there,
+    // or the read that ended with it filled the buffer and the rest is still in the source
+    fn read_key_event(&mut self) -> Option<KeyEvent> {
+        let c = self.pop_char()?;
+        let filled = self.limit == self.buf.len();
+        let key = match c {
+            '\r' | '\n' => KeyEvent::Enter,
+            '\t' => KeyEvent::Tab,
+            '\u{7f}' | '\u{8}' => KeyEvent::Backspace,
+            '\u{0}' => KeyEvent::Ctrl(' '),
+            '\u{1b}' if !self.char_buffered() && !filled => KeyEvent::Escape,
+            '\u{1b}' => return self.read_escape_sequence(),
+            '\u{1}'..='\u{1a}' => KeyEvent::Ctrl((b'a' + c as u8 - 1) as char),
+            c => KeyEvent::Char(c),
+        };
+        Some(key)
+    }
+    // text of a bracketed paste up to the closing sequence
+    fn read_paste(&mut self) -> KeyEvent {
+        const END: &str = "\u{1b}[201~";
+        let mut text = String::new();
+        while let Some(c) = self.pop_char() {
+            text.push(c);
+            if text.ends_with(END) {
+                text.truncate(text.len() - END.len());
+                break;
+            }
+        }
+        KeyEvent::Paste(text)
+    }
+    fn read_escape_sequence(&mut self) -> Option<KeyEvent> {
+        let key = match self.pop_char()? {
+            '[' => {
+                let mut params = String::new();
+                let last = loop {
+                    match self.pop_char() {
+                        Some(c @ ('0'..='9' | ';')) => params.push(c),
+                        Some(c) => break c,
+                        None => return Some(KeyEvent::Unknown(format!("[{}", params))),
+                    }
+                };
+                let code = params.split(';').next().and_then(|p| p.parse::<u8>().ok());
+                match (last, code) {
+                    ('A', _) => KeyEvent::Arrow(Direction::Up),
+                    ('B', _) => KeyEvent::Arrow(Direction::Down),
+                    ('C', _) => KeyEvent::Arrow(Direction::Right),
+                    ('D', _) => KeyEvent::Arrow(Direction::Left),
+                    ('H', _) | ('~', Some(1 | 7)) => KeyEvent::Home,
+                    ('F', _) | ('~', Some(4 | 8)) => KeyEvent::End,
+                    ('~', Some(2)) => KeyEvent::Insert,
+                    ('~', Some(3)) => KeyEvent::Delete,
+                    ('~', Some(5)) => KeyEvent::PageUp,
+                    ('~', Some(6)) => KeyEvent::PageDown,
+                    ('~', Some(n @ 11..=15)) => KeyEvent::F(n - 10),
+                    ('~', Some(n @ 17..=21)) => KeyEvent::F(n - 11),
+                    ('~', Some(n @ 23..=24)) => KeyEvent::F(n - 12),
+                    ('~', Some(200)) => self.read_paste(),
+                    (last, _) => KeyEvent::Unknown(format!("[{}{}", params, last)),
+                }
+            }
+            'O' => match self.pop_char()? {
+                c @ 'P'..='S' => KeyEvent::F(c as u8 - b'P' + 1),
+                'A' => KeyEvent::Arrow(Direction::Up),
+                'B' => KeyEvent::Arrow(Direction::Down),
+                'C' => KeyEvent::Arrow(Direction::Right),
+                'D' => KeyEvent::Arrow(Direction::Left),
+                'H' => KeyEvent::Home,
+                'F' => KeyEvent::End,
+                c => KeyEvent::Unknown(format!("O{}", c)),
+            },
+            c => KeyEvent::Alt(c),
+        };
+        Some(key)
+    }
+    fn next_char_result(&mut self) -> Result<Option<char>, DecodeError> {
+        let c = match self.next_newline()? {
+            Some(c) => c,
+            None => return Ok(None),
+        };
+        if !self.continuation || c != '\\' {
+            return Ok(Some(c));
+        }
+        // a backslash right before a line break joins the lines
+        let at = self.pos;
+        let mut next = String::new();
+        while let Ok(Some(n)) = self.next_newline() {
+            next.push(n);
+            if n != '\r' {
+                break;
+            }
+        }
+        if next == "\n" || next == "\r\n" {
+            return self.next_char_result();
+        }
+        self.unread(&next, at);
+        Ok(Some(c))
+    }
+    fn next_newline(&mut self) -> Result<Option<char>, DecodeError> {
+        let c = match self.next_char_raw()? {
+            Some(c) => c,
+            None => return Ok(None),
+        };
+        if !self.strip_cr || c != '\r' {
+            return Ok(Some(c));
+        }
+        let at = self.pos;
+        match self.next_char_raw() {
+            Ok(Some('\n')) => Ok(Some('\n')),
+            Ok(Some(next)) => {
+                self.unread(next.encode_utf8(&mut [0; 4]), at);
+                Ok(Some(c))
+            }
+            _ => Ok(Some(c)),
+        }
+    }
+    fn line_ending(&self) -> LineEnding {
+        match self.endings {
+            (0, 0, _) => LineEnding::Unknown,
+            (_, 0, _) => LineEnding::Lf,
+            (0, _, _) => LineEnding::CrLf,
+            _ => LineEnding::Mixed,
+        }
+    }
+    fn next_char_raw(&mut self) -> Result<Option<char>, DecodeError> {
+        if self.exhausted() {
+            return Ok(None);
+        }
+        let (c, at) = match self.pushback.pop() {
+            Some(entry) => entry,
+            None => {
+                // skipped invalid bytes still count
+                self.pos.offset = self.consumed;
+                let at = self.pos;
+                let c = match self.decode_char() {
+                    Some(c) => c,
+                    None if !self.error => {
+                        if let Some(handler) = &mut self.error_handler {
+                            handler(&ReadFailure {
+                                token: None,
+                                type_name: "char",
+                                position: at,
+                                end: at,
+                                error: ReadError::Decode(DecodeError::InvalidUtf8(at)),
+                            });
+                        }
+                        return Err(DecodeError::InvalidUtf8(at));
+                    }
+                    None if self.io_error => return Err(DecodeError::Io),
+                    None => return Ok(None),
+                };
+                self.count_ending(c);
+                (c, at)
+            }
+        };
+        self.pos = at.advance(c);
+        if !self.marks.is_empty() {
+            self.journal.push((c, at));
+        }
+        if c == '\n' {
+            for budget in self.budgets.iter_mut() {
+                if let Budget::Lines(n) = budget {
+                    *n -= 1;
+                }
+            }
+        }
+        Ok(Some(c))
+    }
+    // count the line break a char decoded from the source ends
+    fn count_ending(&mut self, c: char) {
+        let (lf, crlf, cr) = &mut self.endings;
+        match c {
+            '\n' if *cr => *crlf += 1,
+            '\n' => *lf += 1,
+            _ => (),
+        }
+        *cr = c == '\r';
+    }
+    // consume the ascii byte at ptr like next_char_raw consumes a char, used by the byte fast paths
+    fn advance_byte(&mut self) {
+        let c = self.buf[self.ptr] as char;
+        self.ptr += 1;
+        self.consumed += 1;
+        self.count_ending(c);
+        self.pos = self.pos.advance(c);
+    }
+    fn mark(&mut self) {
+        self.marks.push((self.journal.len(), self.budgets.clone(), self.stats.tokens));
+    }
+    fn rollback(&mut self) {
+        if let Some((pos, budgets, tokens)) = self.marks.pop() {
+            let consumed = self.journal.split_off(pos);
+            if let Some(&(_, at)) = consumed.first() {
+                self.pos = at;
+            }
+            self.pushback.extend(consumed.into_iter().rev());
+            // views opened or dropped since the mark keep their own limits
+            for (budget, saved) in self.budgets.iter_mut().zip(budgets) {
+                *budget = saved;
+            }
+            self.stats.tokens = tokens;
+        }
+    }
+    fn commit(&mut self) {
+        self.marks.pop();
+        if self.marks.is_empty() {
+            self.journal.clear();
+        }
+    }
+    // drop state a panic in the middle of a read could have left behind, buffered input is kept
+    #[cfg(feature = "std")]
+    fn recover(&mut self) {
+        self.marks.clear();
+        self.journal.clear();
+        self.budgets.clear();
+    }
+    fn decode_char(&mut self) -> Option<char> {
+        let res = self.decode_utf8();
+        if res.is_none() && !self.error {
+            self.stats.decode_errors += 1;
+        }
+        res
+    }
+    // decoder tested on https://onlineutf8tools.com/convert-utf8-to-bytes
+    fn decode_utf8(&mut self) -> Option<char> {
         let c1: u32 = self.pop_byte()? as u32;
         let res: u32;
 
@@ -125,15 +1086,15 @@ impl Stream {
         */
         if (c1 & 0x80) == 0 {
             res = c1;
-            return std::char::from_u32(res);
+            return core::char::from_u32(res);
         }
         /*
             One continuation (128 to 2047)
         */
         if (c1 & 0xE0) == 0xC0 {
             let c2 = self.pop_byte()? as u32;
-            res = ((c1 & 0x1F) << 6) | (c2 & 0x3F << 0);
-            return std::char::from_u32(res);
+            res = ((c1 & 0x1F) << 6) | (c2 & 0x3F);
+            return core::char::from_u32(res);
         }
         /*
             Two continuations (2048 to 55295 and 57344 to 65535)
@@ -141,8 +1102,8 @@ impl Stream {
         else if (c1 & 0xF0) == 0xE0 {
             let c2 = self.pop_byte()? as u32;
             let c3 = self.pop_byte()? as u32;
-            res = ((c1 & 0x0F) << 12) | (c2 & 0x3F << 6) | (c3 & 0x3F << 0);
-            return std::char::from_u32(res);
+            res = ((c1 & 0x0F) << 12) | ((c2 & 0x3F) << 6) | (c3 & 0x3F);
+            return core::char::from_u32(res);
         }
         /*
             Three continuations (65536 to 1114111)
@@ -151,97 +1112,2349 @@ impl Stream {
             let c2 = self.pop_byte()? as u32;
             let c3 = self.pop_byte()? as u32;
             let c4 = self.pop_byte()? as u32;
-            res = (c1 & 0x07 << 18) | (c2 & 0x3F << 12) | (c3 & 0x3F << 6) | (c4 & 0x3F << 0);
-            return std::char::from_u32(res);
+            res = ((c1 & 0x07) << 18) | ((c2 & 0x3F) << 12) | ((c3 & 0x3F) << 6) | (c4 & 0x3F);
+            return core::char::from_u32(res);
         }
         None
     }
-    fn read<T: FromStr>(&mut self) -> Option<T> {
+    fn read<T: FromStr + 'static>(&mut self) -> Option<T> {
+        loop {
+            let token = match self.next_token_for::<T>() {
+                Ok(token) => token,
+                Err(error) => {
+                    self.fail::<T>(None, error);
+                    return None;
+                }
+            };
+            if let Some(value) = self.parse(&token) {
+                return Some(value);
+            }
+            match self.policy {
+                ParsePolicy::Stop => return None,
+                ParsePolicy::Skip => continue,
+                ParsePolicy::UseDefault => return self.default_of(),
+            }
+        }
+    }
+    // default of T for ParsePolicy::UseDefault, registered or of a primitive
+    fn default_of<T: 'static>(&self) -> Option<T> {
+        if let Some(f) = self.defaults.get(&TypeId::of::<T>()).and_then(|f| f.downcast_ref::<fn() -> T>()) {
+            return Some(f());
+        }
+        let mut value: Option<T> = None;
+        let slot: &mut dyn Any = &mut value;
+        macro_rules! primitive_default {
+            ($($t:ty),*) => {
+                $(if let Some(slot) = slot.downcast_mut::<Option<$t>>() {
+                    *slot = Some(<$t>::default());
+                })*
+            };
+        }
+        primitive_default!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, f32, f64, bool, char, String);
+        value
+    }
+    fn register_default<T: Default + 'static>(&mut self) {
+        let f: fn() -> T = T::default;
+        self.defaults.insert(TypeId::of::<T>(), Box::new(f));
+    }
+    // remember why reading a T failed
+    fn fail<T>(&mut self, token: Option<&str>, error: ReadError) {
+        let failure = ReadFailure {
+            token: token.map(String::from),
+            type_name: core::any::type_name::<T>(),
+            position: self.token_start,
+            end: if token.is_some() { self.token_end } else { self.token_start },
+            error,
+        };
+        if let Some(handler) = &mut self.error_handler {
+            handler(&failure);
+        }
+        self.last_failure = Some(failure);
+    }
+    fn parse<T: FromStr + 'static>(&mut self, token: &str) -> Option<T> {
+        let res = self.convert(token);
+        if res.is_none() {
+            self.parse_failed::<T>(token, parse_error::<T>(token));
+        }
+        res
+    }
+    // registered parser of T or FromStr
+    fn convert<T: FromStr + 'static>(&self, token: &str) -> Option<T> {
+        if self.parsers.is_empty() {
+            return T::from_str(token).ok();
+        }
+        match self.parsers.get(&TypeId::of::<T>()).and_then(|p| p.downcast_ref::<Parser<T>>()) {
+            Some(parser) => parser(token),
+            None => T::from_str(token).ok(),
+        }
+    }
+    fn register_parser<T: 'static>(&mut self, f: impl Fn(&str) -> Option<T> + Send + 'static) {
+        let parser: Parser<T> = Box::new(f);
+        self.parsers.insert(TypeId::of::<T>(), Box::new(parser));
+    }
+    fn parse_with<T>(&mut self, token: &str, f: impl FnOnce(&str) -> Option<T>) -> Option<T> {
+        let res = f(token);
+        if res.is_none() {
+            self.parse_failed::<T>(token, ReadError::Parse(token.into()));
+        }
+        res
+    }
+    fn parse_failed<T>(&mut self, token: &str, error: ReadError) {
+        self.stats.parse_failures += 1;
+        self.fail::<T>(Some(token), error);
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            token,
+            target_type = core::any::type_name::<T>(),
+            line = self.token_start.line,
+            column = self.token_start.column,
+            "parse failure"
+        );
+    }
+    // integer token that doesn't fit T, reported by read_saturating and read_wrapping
+    fn read_overflowing<T: FastInt + 'static>(&mut self, saturate: bool) -> Option<T> {
+        let token = self.next_token_for::<T>().inspect_err(|error| self.fail::<T>(None, error.clone())).ok()?;
+        let (negative, digits) = match token.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, token.strip_prefix('+').unwrap_or(&token)),
+        };
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            self.parse_failed::<T>(&token, ReadError::Parse(token.clone()));
+            return None;
+        }
+        let value = if saturate {
+            let abs = digits
+                .bytes()
+                .try_fold(0i128, |v, b| v.checked_mul(10)?.checked_add(i128::from(b - b'0')))
+                .unwrap_or(i128::MAX);
+            T::saturating_from(if negative { -abs } else { abs })
+        } else {
+            let abs = digits.bytes().fold(0u64, |v, b| v.wrapping_mul(10).wrapping_add(u64::from(b - b'0')));
+            T::from_wrapping(abs, negative)
+        };
+        Some(value)
+    }
+    fn read_token(&mut self) -> Option<String> {
+        self.next_token().ok()
+    }
+    fn next_token(&mut self) -> Result<String, ReadError> {
         let mut buf = String::new();
+        self.next_token_into(&mut buf)?;
+        Ok(buf)
+    }
+    // token to parse as T, a char is a single char like in cin >> c
+    fn next_token_for<T: 'static>(&mut self) -> Result<String, ReadError> {
+        let mut buf = String::new();
+        let split = if TypeId::of::<T>() == TypeId::of::<char>() { 1 } else { usize::MAX };
+        self.scan_token(&mut buf, split)?;
+        Ok(buf)
+    }
+    // read the next token into buf, which has to be empty
+    fn next_token_into(&mut self, buf: &mut String) -> Result<(), ReadError> {
+        self.scan_token(buf, usize::MAX)
+    }
+    // the token ends after split chars, the rest is the next token
+    fn scan_token(&mut self, buf: &mut String, split: usize) -> Result<(), ReadError> {
+        if self.ascii_fast() {
+            self.skip_ascii_ws();
+        }
+        let mut len = 0;
+        let max = self.max_token_len.unwrap_or(usize::MAX);
         loop {
+            let at = self.pos;
+            if buf.is_empty() {
+                self.token_start = at;
+            }
             match self.pop_char() {
                 None => break, //maybe eof
                 Some(c) => {
-                    if c.is_whitespace() {
-                        if !buf.is_empty() {
+                    if self.whitespace.contains(c) {
+                        if !self.skipws {
+                            // whitespace is data of its own
+                            if buf.is_empty() {
+                                buf.push(c);
+                                self.token_end = self.pos;
+                            } else {
+                                self.unread(c.encode_utf8(&mut [0; 4]), at);
+                            }
+                            break;
+                        }
+                        if len > 0 {
                             // whitespace after data
                             break;
                         }
                     } else {
-                        buf.push(c);
+                        len += 1;
+                        self.token_end = self.pos;
+                        // the rest of a too long token is dropped
+                        if len <= max {
+                            buf.push(c);
+                        }
+                        if len == split {
+                            break;
+                        }
                     }
                 }
             }
         }
         if buf.is_empty() {
-            return None;
+            return Err(ReadError::Eof);
+        }
+        if len > max {
+            return Err(ReadError::TokenTooLong);
+        }
+        #[cfg(feature = "unicode-normalization")]
+        if self.nfc {
+            *buf = normalize_nfc(core::mem::take(buf));
+        }
+        if self.fold_case && buf.chars().any(char::is_uppercase) {
+            *buf = buf.to_lowercase();
         }
-        T::from_str(&buf).ok()
+        self.stats.tokens += 1;
+        for budget in self.budgets.iter_mut() {
+            if let Budget::Tokens(n) = budget {
+                *n -= 1;
+            }
+        }
+        self.trace_token(buf);
+        Ok(())
     }
-    fn read_line(&mut self) -> Option<String> {
-        let mut buf = String::new();
-        loop {
-            match self.pop_char() {
-                None => break, //might be EOF
-                Some('\n') => break,
-                Some(c) => buf.push(c),
+    // report a token read to tracing and the debug trace
+    #[allow(unused_variables)]
+    fn trace_token(&self, token: &dyn fmt::Display) {
+        #[cfg(feature = "tracing")]
+        tracing::trace!(
+            token = %token,
+            line = self.token_start.line,
+            column = self.token_start.column,
+            "token read"
+        );
+        #[cfg(feature = "std")]
+        if self.trace {
+            eprintln!("rcin: token {:?} at {}:{}", token.to_string(), self.token_start.line, self.token_start.column);
+        }
+    }
+    #[cfg(feature = "bumpalo")]
+    fn read_in<'a>(&mut self, bump: &'a bumpalo::Bump) -> Option<&'a str> {
+        let mut buf = core::mem::take(&mut self.scratch);
+        buf.clear();
+        let res = self.next_token_into(&mut buf).ok().map(|_| &*bump.alloc_str(&buf));
+        self.scratch = buf;
+        res
+    }
+    fn read_int_unchecked<T: FastInt + FromStr + 'static>(&mut self) -> Option<T> {
+        if !self.ascii_fast() {
+            return self.read();
+        }
+        match self.scan_int() {
+            Some(int) => Some(T::from_wrapping(int.value, int.sign == Some(b'-'))),
+            None if self.error => {
+                self.fail::<T>(None, ReadError::Eof);
+                None
+            }
+            None => {
+                // the token stays unread, a peek only fetches it for the report
+                let token = self.peek_token().map(String::from).unwrap_or_default();
+                self.parse_failed::<T>(&token, ReadError::Parse(token.clone()));
+                None
             }
         }
-        if self.error && buf.is_empty() {
-            None
-        } else {
-            Some(buf)
+    }
+    fn read_ints_bulk(&mut self, n: usize) -> Vec<i64> {
+        let mut res = Vec::with_capacity(n);
+        while res.len() < n {
+            let value = if self.ascii_fast() {
+                match self.scan_int() {
+                    Some(int) if int.ended => {
+                        let value = match (int.overflow, int.sign) {
+                            (true, _) => None,
+                            (false, Some(b'-')) => 0i64.checked_sub_unsigned(int.value),
+                            (false, _) => i64::try_from(int.value).ok(),
+                        };
+                        if value.is_none() {
+                            let token = format!("{}", int);
+                            self.parse_failed::<i64>(&token, ReadError::Overflow(token.clone()));
+                        }
+                        value
+                    }
+                    None if self.error => {
+                        self.fail::<i64>(None, ReadError::Eof);
+                        None
+                    }
+                    int => {
+                        let mut token = int.map(|int| format!("{}", int)).unwrap_or_default();
+                        token.push_str(&self.skip_token_rest());
+                        self.parse_failed::<i64>(&token, ReadError::Parse(token.clone()));
+                        None
+                    }
+                }
+            } else {
+                self.read()
+            };
+            match value {
+                Some(value) => res.push(value),
+                None => break,
+            }
         }
+        res
     }
-    fn skip_line(&mut self) {
+    // skip ascii whitespace in buf directly, runs of spaces and tabs are checked 8 bytes at a time
+    fn skip_ascii_ws(&mut self) {
+        // a vertical tab is only unicode whitespace
+        let vt = self.whitespace == WhitespaceMode::Unicode;
+        const SPACES: u64 = u64::from_ne_bytes([b' '; 8]);
+        const TABS: u64 = u64::from_ne_bytes([b'\t'; 8]);
+        const LOW: u64 = u64::from_ne_bytes([0x7f; 8]);
+        // high bit set in every zero byte of x
+        let zero_bytes = |x: u64| !(((x & LOW).wrapping_add(LOW)) | x | LOW);
+        // skipped invalid bytes still count
+        self.pos.offset = self.consumed;
         loop {
-            match self.pop_char() {
-                None => break,
-                Some('\n') => break,
-                _ => (),
+            if self.ptr >= self.limit {
+                self.refill();
+                if self.error {
+                    return;
+                }
+            }
+            while self.ptr < self.limit {
+                if self.ptr + 8 <= self.limit {
+                    let mut word = [0; 8];
+                    word.copy_from_slice(&self.buf[self.ptr..self.ptr + 8]);
+                    let word = u64::from_ne_bytes(word);
+                    if (zero_bytes(word ^ SPACES) | zero_bytes(word ^ TABS)) == !LOW {
+                        self.ptr += 8;
+                        self.consumed += 8;
+                        self.pos.offset += 8;
+                        self.pos.column += 8;
+                        self.endings.2 = false;
+                        continue;
+                    }
+                }
+                match self.buf[self.ptr] {
+                    b' ' | b'\t' | b'\r' | b'\n' | b'\x0c' => self.advance_byte(),
+                    b'\x0b' if vt => self.advance_byte(),
+                    _ => return,
+                }
             }
         }
     }
-    fn valid(&self) -> bool {
-        !self.error
+    // no char level feature is active, so bytes can be consumed directly
+    fn bytes_only(&self) -> bool {
+        self.pushback.is_empty() && self.marks.is_empty() && self.budgets.is_empty() && !self.continuation && self.skipws
     }
-}
+    // bytes can be consumed directly and ascii whitespace is checked byte by byte like the mode does
+    fn ascii_fast(&self) -> bool {
+        self.bytes_only() && !matches!(self.whitespace, WhitespaceMode::Custom(_))
+    }
+    // parse ascii digits straight from the byte buffer as a token and the whitespace after it.
+    // None at the end of input and, with nothing consumed, if the token doesn't start with a digit
+    // or a sign followed by a digit
+    fn scan_int(&mut self) -> Option<ScannedInt> {
+        self.skip_ascii_ws();
+        self.token_start = self.pos;
+        let byte = self.peek_byte().filter(|&b| b.is_ascii_digit() || b == b'-' || b == b'+')?;
+        let cr = self.endings.2;
+        self.advance_byte();
+        let mut int = ScannedInt {
+            value: 0,
+            sign: None,
+            overflow: false,
+            digits: 0,
+            long: String::new(),
+            ended: true,
+        };
+        if byte.is_ascii_digit() {
+            int.value = (byte - b'0') as u64;
+            int.digits = 1;
+        } else {
+            int.sign = Some(byte);
+        }
+        while let Some(b) = self.peek_byte().filter(u8::is_ascii_digit) {
+            if int.digits == ScannedInt::EXACT_DIGITS {
+                int.long = format!("{:01$}", int.value, int.digits);
+            }
+            let (shifted, o1) = int.value.overflowing_mul(10);
+            let (added, o2) = shifted.overflowing_add((b - b'0') as u64);
+            int.value = added;
+            int.overflow |= o1 || o2;
+            int.digits += 1;
+            if int.digits > ScannedInt::EXACT_DIGITS {
+                int.long.push(b as char);
+            }
+            self.advance_byte();
+        }
+        if int.digits == 0 {
+            // a sign alone is left unread
+            self.unread(if byte == b'-' { "-" } else { "+" }, self.token_start);
+            self.endings.2 = cr;
+            return None;
+        }
+        self.token_end = self.pos;
+        let vt = self.whitespace == WhitespaceMode::Unicode;
+        // whitespace after the number is consumed like after a token
+        match self.peek_byte() {
+            Some(b) if matches!(b, b' ' | b'\t' | b'\r' | b'\n' | b'\x0c') || (b == b'\x0b' && vt) => {
+                self.advance_byte();
+            }
+            // a multi-byte char can be unicode whitespace, otherwise it's left unread
+            Some(b) if b >= 0x80 && vt => {
+                let at = self.pos;
+                match self.pop_char() {
+                    Some(c) if self.whitespace.contains(c) => (),
+                    Some(c) => {
+                        self.unread(c.encode_utf8(&mut [0; 4]), at);
+                        int.ended = false;
+                    }
+                    None => int.ended = false,
+                }
+            }
+            Some(_) => int.ended = false,
+            None => (),
+        }
+        self.stats.tokens += 1;
+        self.trace_token(&int);
+        Some(int)
+    }
+    // drop the rest of a token that isn't a number, up to the whitespace after it
+    fn skip_token_rest(&mut self) -> String {
+        let mut rest = String::new();
+        if self.error {
+            return rest;
+        }
+        while let Some(c) = self.pop_char() {
+            if self.whitespace.contains(c) {
+                break;
+            }
+            rest.push(c);
+            self.token_end = self.pos;
+        }
+        rest
+    }
+    // the token buffer is reused, only new symbols allocate
+    fn read_interned(&mut self) -> Option<Symbol> {
+        let mut buf = core::mem::take(&mut self.scratch);
+        buf.clear();
+        let res = self.next_token_into(&mut buf).ok().map(|_| match self.symbol_ids.get(&buf) {
+            Some(&sym) => sym,
+            None => {
+                let sym = Symbol(self.symbols.len());
+                self.symbols.push(buf.clone());
+                self.symbol_ids.insert(buf.clone(), sym);
+                sym
+            }
+        });
+        self.scratch = buf;
+        res
+    }
+    // read n and then collect n items
+    fn read_counted<C, T, F>(&mut self, mut item: F) -> Option<C>
+    where
+        C: core::iter::FromIterator<T>,
+        F: FnMut(&mut Self) -> Option<T>,
+    {
+        let n: usize = self.read()?;
+        (0..n).map(|_| item(self)).collect()
+    }
+    fn read_array<T: FromStr + 'static, const N: usize>(&mut self) -> Option<[T; N]> {
+        let mut ok = true;
+        let values = [(); N].map(|_| {
+            let value = if ok { self.read() } else { None };
+            ok = value.is_some();
+            value
+        });
+        if ok {
+            Some(values.map(|v| v.unwrap()))
+        } else {
+            None
+        }
+    }
+    fn read_into_slice<T: FromStr + 'static>(&mut self, slice: &mut [T]) -> usize {
+        for (i, item) in slice.iter_mut().enumerate() {
+            match self.read() {
+                Some(value) => *item = value,
+                None => return i,
+            }
+        }
+        slice.len()
+    }
+    #[cfg(feature = "std")]
+    fn read_pair<K: FromStr + 'static, V: FromStr + 'static>(&mut self) -> Option<(K, V)> {
+        Some((self.read()?, self.read()?))
+    }
+    fn read_matrix<T: FromStr + 'static>(&mut self, rows: usize, cols: usize) -> Option<Vec<Vec<T>>> {
+        (0..rows)
+            .map(|_| (0..cols).map(|_| self.read()).collect())
+            .collect()
+    }
+    fn read_char_grid(&mut self, rows: usize) -> Option<Vec<Vec<char>>> {
+        (0..rows)
+            .map(|_| {
+                let line = self.read_line()?;
+                Some(line.trim_end_matches('\r').chars().collect())
+            })
+            .collect()
+    }
+    // value with the span of its token
+    fn read_spanned<T: FromStr + 'static>(&mut self) -> Result<(T, Span), ReadError> {
+        let token = self.next_token_for::<T>().inspect_err(|error| self.fail::<T>(None, error.clone()))?;
+        let span = Span::of(self.token_start, self.token_end);
+        match self.parse(&token) {
+            Some(value) => Ok((value, span)),
+            None => Err(parse_error::<T>(&token)),
+        }
+    }
+    // a value has to be the whole token, the rest of a token split after a char is consumed with it
+    fn read_strict<T: FromStr + 'static>(&mut self) -> Result<T, ReadError> {
+        let mut token = self.next_token_for::<T>().inspect_err(|error| self.fail::<T>(None, error.clone()))?;
+        if TypeId::of::<T>() == TypeId::of::<char>() {
+            let at = self.pos;
+            match self.pop_char() {
+                Some(c) if !self.whitespace.contains(c) => {
+                    self.unread(c.encode_utf8(&mut [0; 4]), at);
+                    token.push_str(&self.next_token()?);
+                    let error = ReadError::TrailingGarbage(token.clone());
+                    self.parse_failed::<T>(&token, error.clone());
+                    return Err(error);
+                }
+                Some(c) => self.unread(c.encode_utf8(&mut [0; 4]), at),
+                None => (),
+            }
+        }
+        if let Some(value) = self.convert(&token) {
+            return Ok(value);
+        }
+        let prefix_parses = token.char_indices().skip(1).any(|(i, _)| self.convert::<T>(&token[..i]).is_some());
+        let error = if prefix_parses { ReadError::TrailingGarbage(token.clone()) } else { parse_error::<T>(&token) };
+        self.parse_failed::<T>(&token, error.clone());
+        Err(error)
+    }
+    fn parse_line<T: FromLine>(&mut self) -> Option<T> {
+        let line = self.read_line()?;
+        let whitespace = &self.whitespace;
+        T::from_fields(line.split(|c| whitespace.contains(c)).filter(|f| !f.is_empty()))
+    }
+    fn read_lowercase(&mut self) -> Option<String> {
+        let token = self.read_token()?;
+        Some(token.to_lowercase())
+    }
+    fn read_word(&mut self, max: usize) -> Option<String> {
+        let mut buf = String::new();
+        self.scan_token(&mut buf, max.max(1)).ok()?;
+        Some(buf)
+    }
+    // nothing is consumed if the input ends before n chars
+    fn read_chars(&mut self, n: usize) -> Option<String> {
+        let budgets = self.budgets.clone();
+        self.mark();
+        let out: Option<String> = (0..n).map(|_| self.pop_char()).collect();
+        if out.is_some() {
+            self.commit();
+        } else {
+            self.rollback();
+            self.budgets = budgets;
+        }
+        out
+    }
+    fn read_with<T>(&mut self, manip: impl manip::Manipulator<T>) -> Option<T> {
+        let token = self.read_token()?;
+        self.parse_with(&token, |token| manip.parse(token))
+    }
+    fn read_parsed<T>(&mut self, f: impl FnOnce(&str) -> Option<T>) -> Option<T> {
+        let token = self.read_token()?;
+        self.parse_with(&token, f)
+    }
+    // try the token as is, then in lower, upper and title case
+    fn read_enum<T: FromStr + 'static>(&mut self) -> Option<T> {
+        let token = self.read_token()?;
+        let res = self.convert(&token).or_else(|| {
+            let lower = token.to_lowercase();
+            let mut title: String = lower.chars().take(1).flat_map(char::to_uppercase).collect();
+            title.extend(lower.chars().skip(1));
+            self.convert(&lower)
+                .or_else(|| self.convert(&token.to_uppercase()))
+                .or_else(|| self.convert(&title))
+        });
+        self.parse_with(&token, |_| res)
+    }
+    fn read_either<A: FromStr + 'static, B: FromStr + 'static>(&mut self) -> Option<Either<A, B>> {
+        let budgets = self.budgets.clone();
+        self.mark();
+        let res = self.read_token().and_then(|token| match self.convert::<A>(&token) {
+            Some(a) => Some(Either::Left(a)),
+            None => self.parse(&token).map(Either::Right),
+        });
+        if res.is_some() {
+            self.commit();
+        } else {
+            self.rollback();
+            self.budgets = budgets;
+        }
+        res
+    }
+    fn read_checked<T: FromStr + 'static>(&mut self) -> Result<T, ReadError> {
+        loop {
+            let token = self.next_token_for::<T>().inspect_err(|error| self.fail::<T>(None, error.clone()))?;
+            if let Some(value) = self.parse(&token) {
+                return Ok(value);
+            }
+            match self.policy {
+                ParsePolicy::Stop => return Err(parse_error::<T>(&token)),
+                ParsePolicy::Skip => continue,
+                ParsePolicy::UseDefault => return self.default_of().ok_or_else(|| parse_error::<T>(&token)),
+            }
+        }
+    }
+    fn read_in_range<T, R>(&mut self, range: R) -> Result<T, ReadError>
+    where
+        T: FromStr + 'static + PartialOrd,
+        R: RangeBounds<T>,
+    {
+        loop {
+            let token = self.next_token_for::<T>().inspect_err(|error| self.fail::<T>(None, error.clone()))?;
+            let error = match self.parse(&token) {
+                Some(value) if range.contains(&value) => return Ok(value),
+                Some(_) => {
+                    let error = ReadError::OutOfRange(token.clone());
+                    self.fail::<T>(Some(&token), error.clone());
+                    error
+                }
+                None => parse_error::<T>(&token),
+            };
+            match self.policy {
+                ParsePolicy::Stop => return Err(error),
+                ParsePolicy::Skip => continue,
+                ParsePolicy::UseDefault => return self.default_of().filter(|value| range.contains(value)).ok_or(error),
+            }
+        }
+    }
+    // run f and undo everything it consumed
+    fn peek_with(&mut self, f: impl FnOnce(&mut Self) -> Option<String>) -> Option<&str> {
+        let budgets = self.budgets.clone();
+        self.mark();
+        let res = f(self);
+        self.rollback();
+        self.budgets = budgets;
+        self.peeked = res?;
+        Some(&self.peeked)
+    }
+    fn peek_token(&mut self) -> Option<&str> {
+        self.peek_with(Stream::read_token)
+    }
+    fn peek_line(&mut self) -> Option<&str> {
+        self.peek_with(Stream::read_line)
+    }
+    // the next chars stay in pushback, so later lookaheads don't touch the source again
+    fn lookahead(&mut self, n: usize) -> &[char] {
+        let budgets = self.budgets.clone();
+        self.window.clear();
+        self.mark();
+        while self.window.len() < n.min(MAX_LOOKAHEAD) {
+            match self.pop_char() {
+                Some(c) => self.window.push(c),
+                None => break,
+            }
+        }
+        self.rollback();
+        self.budgets = budgets;
+        &self.window
+    }
+    fn read_unescaped(&mut self) -> Option<String> {
+        let token = self.read_token()?;
+        unescape(&token)
+    }
+    fn read_line(&mut self) -> Option<String> {
+        self.read_line_checked().ok()
+    }
+    // read a line with the editor, whole lines pasted after it are read next from pushback
+    #[cfg(feature = "editor")]
+    fn edit_line(&mut self) -> Option<Result<String, interactive::Interrupt>> {
+        let line = editor::read_line(self.history.as_mut(), self.completer.as_ref(), &mut self.draft).ok();
+        let pasted = self.draft.rfind('\n').map_or(0, |i| i + 1);
+        let lines: String = self.draft.drain(..pasted).collect();
+        let mut pos = self.pos;
+        let mut entries = Vec::new();
+        for c in lines.chars() {
+            entries.push((c, pos));
+            pos = pos.advance(c);
+        }
+        self.pushback.extend(entries.into_iter().rev());
+        line
+    }
+    // the next line comes from the terminal and nothing is buffered
+    #[cfg(feature = "editor")]
+    fn editor_owns_input(&self) -> bool {
+        self.on_stdin && self.ptr >= self.limit && self.pushback.is_empty() && io::IsTerminal::is_terminal(&io::stdin())
+    }
+    // borrow the line from buf if it's there completely and no char level feature is active
+    fn read_line_borrowed(&mut self) -> Option<Cow<'_, str>> {
+        #[cfg(feature = "unicode-normalization")]
+        let normalized = self.nfc;
+        #[cfg(not(feature = "unicode-normalization"))]
+        let normalized = false;
+        let plain = self.pushback.is_empty()
+            && !normalized
+            && self.marks.is_empty()
+            && !self.continuation
+            && self.max_line_len.is_none()
+            && !self.exhausted();
+        if plain && self.ptr >= self.limit {
+            self.refill();
+            // the source ended, reading it again could block on a terminal
+            if self.error {
+                return None;
+            }
+        }
+        // a line cut by the end of buf is completed behind the unconsumed part
+        while plain && !self.buf[self.ptr..self.limit].contains(&b'\n') && (self.ptr > 0 || self.limit < self.buf.len()) {
+            let len = self.limit - self.ptr;
+            self.refill();
+            if self.limit == len {
+                break;
+            }
+        }
+        let start = self.ptr;
+        #[cfg(feature = "std")]
+        let at = self.pos;
+        let rest = &self.buf[start..self.limit];
+        let end = match rest.iter().position(|&b| b == b'\n') {
+            Some(end) if plain && core::str::from_utf8(&rest[..end]).is_ok() => start + end,
+            _ => return self.read_line().map(Cow::Owned),
+        };
+        let mut line = core::str::from_utf8(&self.buf[start..end]).unwrap_or_default();
+        let cr = line.chars().next_back().map_or(self.endings.2, |c| c == '\r');
+        if cr {
+            self.endings.1 += 1;
+        } else {
+            self.endings.0 += 1;
+        }
+        self.endings.2 = false;
+        if cr && self.strip_cr {
+            line = &line[..line.len() - 1];
+        }
+        self.ptr = end + 1;
+        self.consumed += end + 1 - start;
+        self.pos = Position {
+            offset: self.consumed,
+            line: self.pos.line + 1,
+            column: 1,
+        };
+        for budget in self.budgets.iter_mut() {
+            if let Budget::Lines(n) = budget {
+                *n -= 1;
+            }
+        }
+        #[cfg(feature = "std")]
+        {
+            if let Some(history) = &mut self.history {
+                history.push(line);
+            }
+            if self.trace {
+                eprintln!("rcin: line {:?} at {}:{}", line, at.line, at.column);
+            }
+        }
+        Some(Cow::Borrowed(line))
+    }
+    fn read_line_checked(&mut self) -> Result<String, ReadError> {
+        #[cfg(feature = "std")]
+        let at = self.pos;
+        let line = self.read_until_checked('\n')?;
+        #[cfg(feature = "std")]
+        {
+            if let Some(history) = &mut self.history {
+                history.push(&line);
+            }
+            if self.trace {
+                eprintln!("rcin: line {:?} at {}:{}", line, at.line, at.column);
+            }
+        }
+        Ok(line)
+    }
+    // empty answers take the default, the label shows it
+    #[cfg(feature = "std")]
+    fn prompt_with_default<T>(&mut self, out: &mut dyn io::Write, label: &str, default: T) -> Result<T, ReadError>
+    where
+        T: FromStr + 'static + fmt::Display,
+    {
+        let _ = write!(out, "{} [{}]: ", label, default);
+        let _ = out.flush();
+        let line = self.read_line_checked()?;
+        let answer = line.trim();
+        if answer.is_empty() {
+            return Ok(default);
+        }
+        match self.parse(answer) {
+            Some(value) => Ok(value),
+            None => Err(ReadError::Parse(answer.to_string())),
+        }
+    }
+    fn read_until(&mut self, delim: char) -> Option<String> {
+        self.read_until_checked(delim).ok()
+    }
+    fn read_until_checked(&mut self, delim: char) -> Result<String, ReadError> {
+        let (buf, found) = self.read_until_raw(delim)?;
+        #[cfg(feature = "unicode-normalization")]
+        let buf = if self.nfc { normalize_nfc(buf) } else { buf };
+        if !found && !self.valid() && buf.is_empty() {
+            Err(ReadError::Eof)
+        } else {
+            Ok(buf)
+        }
+    }
+    // the first line that fails pred is left in the stream
+    fn read_lines_while(&mut self, mut pred: impl FnMut(&str) -> bool) -> Vec<String> {
+        let mut lines = Vec::new();
+        loop {
+            let at = self.pos;
+            let (mut line, found) = match self.read_until_raw('\n') {
+                Ok(res) => res,
+                Err(_) => break,
+            };
+            if !found && line.is_empty() && !self.valid() {
+                break;
+            }
+            if !pred(&line) {
+                if found {
+                    line.push('\n');
+                }
+                self.unread(&line, at);
+                break;
+            }
+            lines.push(line);
+        }
+        lines
+    }
+    // a line and the following ones up to the next line matching is_header
+    fn read_record(&mut self, mut is_header: impl FnMut(&str) -> bool) -> Option<Vec<String>> {
+        let mut record = vec![self.read_line()?];
+        record.extend(self.read_lines_while(|line| !is_header(line)));
+        Some(record)
+    }
+    fn read_indented_block(&mut self, indent: usize) -> Vec<String> {
+        let blank = |line: &str| line.trim().is_empty();
+        let indented = |line: &str| {
+            !blank(line) && line.len() >= indent && line.bytes().take(indent).all(|b| b == b' ')
+        };
+        let mut lines = self.read_lines_while(indented);
+        loop {
+            // blank lines are only part of the block if an indented line follows them
+            let budgets = self.budgets.clone();
+            self.mark();
+            self.read_lines_while(blank);
+            let more = self.read_lines_while(indented);
+            if more.is_empty() {
+                self.rollback();
+                self.budgets = budgets;
+                break;
+            }
+            self.commit();
+            lines.extend(more);
+        }
+        for line in lines.iter_mut() {
+            line.drain(..indent);
+        }
+        lines
+    }
+    // returns the chars before delim and whether delim was found
+    fn read_until_raw(&mut self, delim: char) -> Result<(String, bool), ReadError> {
+        let mut buf = String::new();
+        let mut len = 0;
+        let max = self.max_line_len.unwrap_or(usize::MAX);
+        let found = loop {
+            match self.pop_char() {
+                None => break false, //might be EOF
+                Some(c) if c == delim => break true,
+                Some(c) => {
+                    len += 1;
+                    // the rest of a too long line is dropped
+                    if len <= max {
+                        buf.push(c);
+                    }
+                }
+            }
+        };
+        if len > max {
+            return Err(ReadError::LineTooLong);
+        }
+        Ok((buf, found))
+    }
+    // return the most recently consumed chars, that started at the given position, to the stream
+    fn unread(&mut self, s: &str, at: Position) {
+        let mut entries = Vec::new();
+        let mut pos = at;
+        for c in s.chars() {
+            entries.push((c, pos));
+            pos = pos.advance(c);
+            if !self.marks.is_empty() {
+                self.journal.pop();
+            }
+        }
+        self.pushback.extend(entries.into_iter().rev());
+        self.pos = at;
+    }
+    #[cfg(feature = "unicode-segmentation")]
+    fn read_grapheme(&mut self) -> Option<String> {
+        use unicode_segmentation::UnicodeSegmentation;
+        let mut buf = String::new();
+        let mut at = self.pos;
+        while let Some(c) = self.pop_char() {
+            buf.push(c);
+            let first = buf.graphemes(true).next().map_or(0, str::len);
+            if first < buf.len() {
+                // c starts the next grapheme
+                self.unread(&buf[first..], at);
+                buf.truncate(first);
+                break;
+            }
+            at = self.pos;
+        }
+        if buf.is_empty() {
+            None
+        } else {
+            Some(buf)
+        }
+    }
+    fn skip_until(&mut self, needle: char, include: bool) -> bool {
+        let mut at;
+        loop {
+            at = self.pos;
+            match self.pop_char() {
+                Some(c) if c == needle => break,
+                Some(_) => (),
+                None if !self.valid() => return false,
+                None => (),
+            }
+        }
+        if !include {
+            self.unread(needle.encode_utf8(&mut [0; 4]), at);
+        }
+        true
+    }
+    fn skip_until_str(&mut self, needle: &str, include: bool) -> bool {
+        let needle: Vec<char> = needle.chars().collect();
+        let mut window = VecDeque::with_capacity(needle.len());
+        while !window.iter().map(|&(c, _)| c).eq(needle.iter().cloned()) {
+            let at = self.pos;
+            match self.pop_char() {
+                Some(c) => {
+                    if window.len() == needle.len() {
+                        window.pop_front();
+                    }
+                    window.push_back((c, at));
+                }
+                None if !self.valid() => return false,
+                None => (),
+            }
+        }
+        if !include {
+            let at = window.front().map_or(self.pos, |&(_, at)| at);
+            self.unread(&window.into_iter().map(|(c, _)| c).collect::<String>(), at);
+        }
+        true
+    }
+    #[cfg(feature = "regex")]
+    fn find_match(&mut self, regex: &regex::Regex, consume: bool) -> Option<String> {
+        loop {
+            let at = self.pos;
+            let (mut line, found) = self.read_until_raw('\n').ok()?;
+            if found {
+                line.push('\n');
+            } else if line.is_empty() && !self.valid() {
+                return None;
+            }
+            if let Some(m) = regex.find(&line) {
+                let (start, end) = (m.start(), m.end());
+                let matched = line[start..end].to_string();
+                let rest = if consume { end } else { start };
+                let rest_at = line[..rest].chars().fold(at, Position::advance);
+                self.unread(&line[rest..], rest_at);
+                return Some(matched);
+            }
+        }
+    }
+    #[cfg(feature = "regex")]
+    fn read_match(&mut self, regex: &regex::Regex) -> Option<String> {
+        self.find_match(regex, true)
+    }
+    #[cfg(feature = "regex")]
+    fn skip_until_match(&mut self, regex: &regex::Regex) -> bool {
+        self.find_match(regex, false).is_some()
+    }
+    // feed lines to a streaming parser until it stops returning Incomplete
+    #[cfg(feature = "nom")]
+    fn parse_nom<O>(&mut self, mut parser: impl FnMut(&str) -> nom::IResult<&str, O>) -> Result<O, ReadError> {
+        let at = self.pos;
+        let mut input = String::new();
+        loop {
+            let (line, found) = match self.read_until_raw('\n') {
+                Ok(read) => read,
+                Err(err) => {
+                    self.unread(&input, at);
+                    return Err(err);
+                }
+            };
+            let eof = !found && line.is_empty() && !self.valid();
+            input.push_str(&line);
+            if found {
+                input.push('\n');
+            }
+            let err = match parser(&input) {
+                Ok((rest, out)) => {
+                    let used = input.len() - rest.len();
+                    let rest_at = input[..used].chars().fold(at, Position::advance);
+                    self.unread(rest, rest_at);
+                    return Ok(out);
+                }
+                Err(nom::Err::Incomplete(_)) if !eof => continue,
+                Err(nom::Err::Incomplete(_)) => ReadError::Eof,
+                Err(nom::Err::Error(e)) | Err(nom::Err::Failure(e)) => {
+                    ReadError::Parse(String::from(e.input.lines().next().unwrap_or_default()))
+                }
+            };
+            self.unread(&input, at);
+            return Err(err);
+        }
+    }
+    fn read_csv_record(&mut self, delimiter: char) -> Option<Vec<String>> {
+        self.read_csv_record_checked(delimiter).ok()
+    }
+    // invalid utf8 fails the record, the rest of it stays unread
+    fn read_csv_record_checked(&mut self, delimiter: char) -> Result<Vec<String>, ReadError> {
+        let mut record = Vec::new();
+        let mut field = String::new();
+        let mut quoted = false; // inside a quoted field
+        let mut closed = false; // right after a closing quote
+        let mut empty = true;
+        loop {
+            let c = match self.next_char_result().map_err(ReadError::Decode)? {
+                None => break, //might be EOF
+                Some(c) => c,
+            };
+            empty = false;
+            if quoted {
+                if c == '"' {
+                    quoted = false;
+                    closed = true;
+                } else {
+                    field.push(c);
+                }
+                continue;
+            }
+            match c {
+                // doubled quote inside a quoted field
+                '"' if closed => {
+                    field.push('"');
+                    quoted = true;
+                }
+                '"' if field.is_empty() => quoted = true,
+                '\n' => break,
+                // only the \r of a \r\n line break is dropped
+                '\r' => {
+                    let at = self.pos;
+                    match self.next_char_result().map_err(ReadError::Decode)? {
+                        Some('\n') | None => break,
+                        Some(next) => {
+                            field.push('\r');
+                            self.unread(next.encode_utf8(&mut [0; 4]), at);
+                        }
+                    }
+                }
+                _ if c == delimiter => record.push(core::mem::take(&mut field)),
+                _ => field.push(c),
+            }
+            closed = false;
+        }
+        if empty {
+            return Err(ReadError::Eof);
+        }
+        record.push(field);
+        Ok(record)
+    }
+    fn read_header(&mut self, delimiter: char) -> Option<Vec<String>> {
+        let header = self.read_csv_record(delimiter)?;
+        self.header = Some((delimiter, header.clone()));
+        Some(header)
+    }
+    #[cfg(feature = "std")]
+    fn read_record_map(&mut self) -> Option<HashMap<String, String>> {
+        let delimiter = self.header.as_ref()?.0;
+        let record = self.read_csv_record(delimiter)?;
+        let (_, header) = self.header.as_ref()?;
+        Some(header.iter().cloned().zip(record).collect())
+    }
+    #[cfg(feature = "serde_json")]
+    fn read_json<T: serde::de::DeserializeOwned>(&mut self) -> Option<T> {
+        loop {
+            let at = self.pos;
+            let line = self.read_line()?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let res = serde_json::from_str(&line).ok();
+            if res.is_none() {
+                // the line is reported like a token that didn't parse
+                self.token_start = at;
+                self.token_end = line.chars().fold(at, Position::advance);
+                self.parse_failed::<T>(&line, ReadError::Parse(line.clone()));
+            }
+            return res;
+        }
+    }
+    fn read_to_string(&mut self) -> String {
+        let mut buf = String::new();
+        while self.valid() {
+            // undecodable bytes are skipped
+            if let Some(c) = self.pop_char() {
+                buf.push(c);
+            }
+        }
+        buf
+    }
+    fn skip_line(&mut self) {
+        loop {
+            match self.pop_char() {
+                None => break,
+                Some('\n') => break,
+                _ => (),
+            }
+        }
+    }
+    fn valid(&self) -> bool {
+        !self.exhausted() && (!self.error || !self.pushback.is_empty())
+    }
+    // true when a take_tokens/take_lines budget is used up
+    fn exhausted(&self) -> bool {
+        self.budgets.iter().any(|b| b.left() == 0)
+    }
+    // hexdump of the next n bytes, unread chars first and the buffer after them
+    fn debug_peek(&mut self, n: usize) -> String {
+        if self.pushback.is_empty() && self.ptr >= self.limit && !self.error {
+            self.refill();
+        }
+        let mut bytes = Vec::new();
+        for (c, _) in self.pushback.iter().rev() {
+            bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+        }
+        bytes.extend_from_slice(&self.buf[self.ptr..self.limit]);
+        bytes.truncate(n);
+        let mut out = String::new();
+        for (i, row) in bytes.chunks(16).enumerate() {
+            let mut hex = String::new();
+            for (j, b) in row.iter().enumerate() {
+                hex.push_str(if j == 8 { "  " } else { " " });
+                hex.push_str(&format!("{:02x}", b));
+            }
+            let ascii: String = row.iter().map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' }).collect();
+            if i > 0 {
+                out.push('\n');
+            }
+            out.push_str(&format!("{:08x} {:<49}  |{}|", self.pos.offset + i * 16, hex, ascii));
+        }
+        out
+    }
+}
+
+// an integer token that doesn't parse as an integer type is out of its range
+fn parse_error<T: 'static>(token: &str) -> ReadError {
+    let ints = [
+        TypeId::of::<i8>(), TypeId::of::<i16>(), TypeId::of::<i32>(), TypeId::of::<i64>(), TypeId::of::<i128>(),
+        TypeId::of::<isize>(), TypeId::of::<u8>(), TypeId::of::<u16>(), TypeId::of::<u32>(), TypeId::of::<u64>(),
+        TypeId::of::<u128>(), TypeId::of::<usize>(),
+    ];
+    let digits = token.strip_prefix('-').or_else(|| token.strip_prefix('+')).unwrap_or(token);
+    if ints.contains(&TypeId::of::<T>()) && !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()) {
+        ReadError::Overflow(token.into())
+    } else {
+        ReadError::Parse(token.into())
+    }
+}
+
+/// Reason a read failed
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ReadError {
+    /// No token left, the source returned EOF or an error
+    Eof,
+    /// Token can't be parsed as the requested type
+    Parse(String),
+    /// Integer token doesn't fit the requested type
+    Overflow(String),
+    /// Token starts with a value followed by garbage, like `1GARBAGE`, returned by `read_strict`
+    TrailingGarbage(String),
+    /// Token was parsed, but is out of the requested range
+    OutOfRange(String),
+    /// Token is longer than the stream's max_token_len, it was skipped
+    TokenTooLong,
+    /// Line is longer than the stream's max_line_len, it was skipped
+    LineTooLong,
+    /// The global stream is used by another thread or by a read further up on this thread
+    Busy,
+    /// Input isn't valid utf8 or the source failed, returned by `events` and reported to the error handler
+    Decode(DecodeError),
+}
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReadError::Eof => write!(f, "no token left"),
+            ReadError::Parse(token) => write!(f, "invalid token '{}'", token),
+            ReadError::Overflow(token) => write!(f, "integer '{}' doesn't fit the type", token),
+            ReadError::TrailingGarbage(token) => write!(f, "garbage after the value in '{}'", token),
+            ReadError::OutOfRange(token) => write!(f, "token '{}' is out of range", token),
+            ReadError::TokenTooLong => write!(f, "token is too long"),
+            ReadError::LineTooLong => write!(f, "line is too long"),
+            ReadError::Busy => write!(f, "stream is busy"),
+            ReadError::Decode(error) => write!(f, "{}", error),
+        }
+    }
+}
+#[cfg(feature = "std")]
+impl std::error::Error for ReadError {}
+
+/// Details of the last failed read, returned by `last_failure`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReadFailure {
+    /// Token that didn't parse, None if there was no token
+    pub token: Option<String>,
+    /// Name of the requested type
+    pub type_name: &'static str,
+    /// Position of the token
+    pub position: Position,
+    /// Position after the token, the same as position if there was no token
+    pub end: Position,
+    /// Reason of the failure
+    pub error: ReadError,
+}
+impl fmt::Display for ReadFailure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "reading {} at {}:{} failed: {}", self.type_name, self.position.line, self.position.column, self.error)
+    }
+}
+impl ReadFailure {
+    /// Span of the token, empty if there was no token
+    pub fn span(&self) -> Span {
+        Span::of(self.position, self.end)
+    }
+    /// Render like a compiler error with the line of the token and carets under it
+    ///
+    /// source is the input read by the stream, without it or if it doesn't match only the message is rendered
+    ///
+    /// ``` rust
+    ///     use rcin::RInStream;
+    ///
+    ///     let config = "name = db\nport = 80x0\n";
+    ///     let mut reader = RInStream::from_source(Box::new(config.as_bytes()));
+    ///     reader.skip_line();
+    ///     reader.read::<String>();
+    ///     reader.read::<char>();
+    ///     assert_eq!(reader.read::<u16>(), None);
+    ///     let report = reader.last_failure().unwrap().render(config);
+    ///     assert_eq!(report, "\
+    /// error: reading u16 failed: invalid token '80x0'
+    ///  --> 2:8
+    ///   |
+    /// 2 | port = 80x0
+    ///   |        ^^^^
+    /// ");
+    /// ```
+    pub fn render(&self, source: &str) -> String {
+        let mut out = format!("error: reading {} failed: {}\n", self.type_name, self.error);
+        let line_no = format!("{}", self.position.line);
+        let gutter = " ".repeat(line_no.len());
+        out += &format!("{}--> {}:{}\n", gutter, line_no, self.position.column);
+        let offset = self.position.offset;
+        let before = match source.get(..offset) {
+            Some(before) => before,
+            None => return out,
+        };
+        let start = before.rfind('\n').map_or(0, |i| i + 1);
+        let end = source[offset..].find('\n').map_or(source.len(), |i| offset + i);
+        let line = source[start..end].trim_end_matches('\r');
+        // tabs are kept so the carets line up with the text
+        let pad: String = source[start..offset].chars().map(|c| if c == '\t' { '\t' } else { ' ' }).collect();
+        let width = self.token.as_deref().map_or(0, |t| t.chars().count()).max(1);
+        out += &format!("{} |\n{} | {}\n{} | {}{}\n", gutter, line_no, line, gutter, pad, "^".repeat(width));
+        out
+    }
+}
+#[cfg(feature = "std")]
+impl std::error::Error for ReadFailure {}
+
+/// Why `next_char_result` returned no char
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The source returned an error
+    Io,
+    /// Bytes starting at the position aren't valid utf8, they were skipped
+    InvalidUtf8(Position),
+}
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecodeError::Io => write!(f, "source error"),
+            DecodeError::InvalidUtf8(at) => write!(f, "invalid utf8 at byte {}", at.offset),
+        }
+    }
+}
+#[cfg(feature = "std")]
+impl std::error::Error for DecodeError {}
+
+/// Location in the input
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Position {
+    /// Byte offset from the start
+    pub offset: usize,
+    /// Line number, starting at 1
+    pub line: usize,
+    /// Char number in line, starting at 1
+    pub column: usize,
+}
+impl Position {
+    fn start() -> Self {
+        Position {
+            offset: 0,
+            line: 1,
+            column: 1,
+        }
+    }
+    // position after c
+    fn advance(self, c: char) -> Self {
+        let offset = self.offset + c.len_utf8();
+        if c == '\n' {
+            Position {
+                offset,
+                line: self.line + 1,
+                column: 1,
+            }
+        } else {
+            Position {
+                offset,
+                column: self.column + 1,
+                ..self
+            }
+        }
+    }
+}
+
+/// Bytes of a token in the input with the line and column of its start
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Span {
+    /// Byte offset of the first byte
+    pub byte_start: usize,
+    /// Byte offset after the last byte
+    pub byte_end: usize,
+    /// Line number, starting at 1
+    pub line: usize,
+    /// Char number in line, starting at 1
+    pub col: usize,
+}
+impl Span {
+    // the token can differ from the input it was read from, if it was normalized or truncated
+    fn of(start: Position, end: Position) -> Self {
+        Span {
+            byte_start: start.offset,
+            byte_end: end.offset,
+            line: start.line,
+            col: start.column,
+        }
+    }
+    /// Number of bytes
+    pub fn len(&self) -> usize {
+        self.byte_end - self.byte_start
+    }
+    /// Whether the span covers no bytes
+    pub fn is_empty(&self) -> bool {
+        self.byte_end == self.byte_start
+    }
+}
+
+/// Byte offsets of all lines of a source
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LineIndex {
+    offsets: Vec<u64>,
+}
+#[cfg(feature = "std")]
+impl LineIndex {
+    /// Scan the source once and record where every line starts
+    pub fn build<R: Read>(mut src: R) -> io::Result<Self> {
+        let mut offsets = vec![0];
+        let mut buf = vec![0; DEFAULT_BUF_SIZE];
+        let mut total = 0;
+        loop {
+            let n = match src.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            for (i, &b) in buf[..n].iter().enumerate() {
+                if b == b'\n' {
+                    offsets.push(total + i as u64 + 1);
+                }
+            }
+            total += n as u64;
+        }
+        // a trailing newline doesn't start a new line
+        if total > 0 && offsets.last() == Some(&total) {
+            offsets.pop();
+        }
+        Ok(LineIndex { offsets })
+    }
+    /// Number of lines
+    pub fn len(&self) -> usize {
+        self.offsets.len()
+    }
+    /// True if there are no lines
+    pub fn is_empty(&self) -> bool {
+        self.offsets.is_empty()
+    }
+    /// Byte offset of the line, lines start at 1
+    pub fn offset(&self, line: usize) -> Option<u64> {
+        self.offsets.get(line.checked_sub(1)?).copied()
+    }
+    // line containing the offset and the offset of its start
+    fn line_at(&self, offset: u64) -> (usize, u64) {
+        let line = self.offsets.partition_point(|&start| start <= offset).max(1);
+        (line, self.offsets.get(line - 1).copied().unwrap_or(0))
+    }
+}
+
+/// Amount of consumed input, passed to the `on_progress` callback
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Progress {
+    /// Number of bytes consumed
+    pub consumed: u64,
+    /// Size of the source, known for files
+    pub total: Option<u64>,
+}
+
+/// Counters collected by a stream
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// Number of successful reads from the source
+    pub refills: u64,
+    /// Number of bytes read from the source
+    pub bytes_read: u64,
+    /// Number of extracted tokens
+    pub tokens: u64,
+    /// Number of tokens that couldn't be parsed as the requested type
+    pub parse_failures: u64,
+    /// Number of byte sequences that aren't valid utf8
+    pub decode_errors: u64,
+}
+
+/// Chars that separate tokens
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum WhitespaceMode {
+    /// Only ascii whitespace (space, tab, newline, ...), the fastest check
+    AsciiOnly,
+    /// All unicode whitespace
+    #[default]
+    Unicode,
+    /// Only the given chars
+    Custom(BTreeSet<char>),
+}
+impl WhitespaceMode {
+    fn contains(&self, c: char) -> bool {
+        match self {
+            WhitespaceMode::AsciiOnly => c.is_ascii_whitespace(),
+            WhitespaceMode::Unicode => c.is_whitespace(),
+            WhitespaceMode::Custom(set) => set.contains(&c),
+        }
+    }
+}
+
+/// What `read` does with a token that doesn't parse
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ParsePolicy {
+    /// Fail the read, the token is consumed
+    #[default]
+    Stop,
+    /// Drop the token and read the next one
+    Skip,
+    /// Return `Default::default()` of primitives and of types registered with `register_default`, others fail
+    UseDefault,
+}
+
+/// Integer that can be read by `read_int_unchecked`, `read_saturating` and `read_wrapping`
+pub trait FastInt: Copy {
+    /// Convert the absolute value read with wrapping arithmetic
+    fn from_wrapping(value: u64, negative: bool) -> Self;
+    /// Convert the value clamped to the range of the type
+    fn saturating_from(value: i128) -> Self;
+}
+macro_rules! impl_fast_int {
+    ($($t:ty)*) => {$(
+        impl FastInt for $t {
+            fn from_wrapping(value: u64, negative: bool) -> Self {
+                (if negative { value.wrapping_neg() } else { value }) as $t
+            }
+            fn saturating_from(value: i128) -> Self {
+                value.clamp(<$t>::MIN as i128, <$t>::MAX as i128) as $t
+            }
+        }
+    )*};
+}
+impl_fast_int!(u8 u16 u32 u64 usize i8 i16 i32 i64 isize);
+
+/// Fields of one line, read by `parse_line`
+pub trait FromLine: Sized {
+    /// Parse all fields, None if one doesn't parse or their number doesn't fit
+    fn from_fields<'a>(fields: impl Iterator<Item = &'a str>) -> Option<Self>;
+}
+impl<T: FromStr> FromLine for Vec<T> {
+    fn from_fields<'a>(fields: impl Iterator<Item = &'a str>) -> Option<Self> {
+        fields.map(|f| f.parse().ok()).collect()
+    }
+}
+macro_rules! impl_from_line {
+    ($($t:ident)*) => {
+        impl<$($t: FromStr),*> FromLine for ($($t,)*) {
+            fn from_fields<'a>(mut fields: impl Iterator<Item = &'a str>) -> Option<Self> {
+                let value = ($(fields.next()?.parse::<$t>().ok()?,)*);
+                match fields.next() {
+                    Some(_) => None,
+                    None => Some(value),
+                }
+            }
+        }
+    };
+}
+impl_from_line!(A);
+impl_from_line!(A B);
+impl_from_line!(A B C);
+impl_from_line!(A B C D);
+impl_from_line!(A B C D E);
+impl_from_line!(A B C D E F);
+impl_from_line!(A B C D E F G);
+impl_from_line!(A B C D E F G H);
+
+/// Interned token, returned by `read_interned`
+///
+/// Symbols of one stream are equal if their tokens are equal
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Symbol(usize);
+
+/// Line breaks seen in the input, returned by `detect_line_ending`
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LineEnding {
+    /// No line break was read yet
+    Unknown,
+    /// Only `\n`
+    Lf,
+    /// Only `\r\n`
+    CrLf,
+    /// Both kinds
+    Mixed,
+}
+
+/// Text encoding of a file
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    /// Utf8, which includes ascii
+    Utf8,
+    /// Utf16 little endian
+    Utf16Le,
+    /// Utf16 big endian
+    Utf16Be,
+    /// Every byte is a char, the fallback for text that isn't valid utf8
+    Latin1,
+}
+
+// composed form, so text typed on macOS compares equal to literals
+#[cfg(feature = "unicode-normalization")]
+fn normalize_nfc(text: String) -> String {
+    use unicode_normalization::UnicodeNormalization;
+    if unicode_normalization::is_nfc(&text) {
+        text
+    } else {
+        text.nfc().collect()
+    }
+}
+
+// encoding of a byte order mark with its length, or utf16 recognized by the zero bytes of ascii chars
+#[cfg(feature = "std")]
+fn sniff_encoding(sample: &[u8]) -> Option<(Encoding, usize)> {
+    match sample {
+        [0xEF, 0xBB, 0xBF, ..] => return Some((Encoding::Utf8, 3)),
+        [0xFF, 0xFE, ..] => return Some((Encoding::Utf16Le, 2)),
+        [0xFE, 0xFF, ..] => return Some((Encoding::Utf16Be, 2)),
+        _ => (),
+    }
+    let pairs = sample.len() / 2;
+    let zeros = |parity: usize| sample.iter().skip(parity).step_by(2).filter(|&&b| b == 0).count();
+    match (zeros(0), zeros(1)) {
+        (0, odd) if pairs > 0 && odd * 2 > pairs => Some((Encoding::Utf16Le, 0)),
+        (even, 0) if pairs > 0 && even * 2 > pairs => Some((Encoding::Utf16Be, 0)),
+        _ => None,
+    }
+}
+
+/// Key pressed on a terminal, returned by `read_key_event`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum KeyEvent {
+    /// Printable char
+    Char(char),
+    /// Letter with Ctrl, like `Ctrl('c')`
+    Ctrl(char),
+    /// Char with Alt, sent as escape followed by the char
+    Alt(char),
+    /// Enter, sent as `\r` in raw mode
+    Enter,
+    /// Tab
+    Tab,
+    /// Backspace, sent as DEL or `\x08`
+    Backspace,
+    /// Escape without a sequence after it
+    Escape,
+    /// Delete
+    Delete,
+    /// Insert
+    Insert,
+    /// Home
+    Home,
+    /// End
+    End,
+    /// Page Up
+    PageUp,
+    /// Page Down
+    PageDown,
+    /// Arrow key, modifiers like in Ctrl+Up are dropped
+    Arrow(Direction),
+    /// Function key F1 to F12
+    F(u8),
+    /// Text pasted with bracketed paste enabled, line breaks in it don't act as Enter
+    Paste(String),
+    /// Escape sequence that isn't known, without the escape
+    Unknown(String),
+}
+
+/// Direction of an arrow key
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Direction {
+    /// Up
+    Up,
+    /// Down
+    Down,
+    /// Left
+    Left,
+    /// Right
+    Right,
+}
+
+/// Value of one of two types, returned by `read_either`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Either<A, B> {
+    /// Token parsed as the first type
+    Left(A),
+    /// Token parsed as the second type
+    Right(B),
+}
+
+/// Low level event of the input, returned by `events`
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Event {
+    /// First char of a token
+    TokenStart(Position),
+    /// Position right after the last char of a token
+    TokenEnd(Position),
+    /// Line break, it also ends a token
+    Newline(Position),
+    /// Invalid utf8 was skipped
+    DecodeError(Position),
+}
+
+/*
+    Converts escape sequences (\n, \t, \uXXXX, ...) into real characters
+*/
+fn unescape(token: &str) -> Option<String> {
+    let mut res = String::with_capacity(token.len());
+    let mut chars = token.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            res.push(c);
+            continue;
+        }
+        match chars.next()? {
+            'n' => res.push('\n'),
+            't' => res.push('\t'),
+            'r' => res.push('\r'),
+            '0' => res.push('\0'),
+            's' => res.push(' '),
+            '\\' => res.push('\\'),
+            '\'' => res.push('\''),
+            '"' => res.push('"'),
+            'u' => {
+                let code = match hex4(&mut chars)? {
+                    // utf16 surrogate pair, the low half has to follow as another \u
+                    high @ 0xD800..=0xDBFF => {
+                        if chars.next()? != '\\' || chars.next()? != 'u' {
+                            return None;
+                        }
+                        match hex4(&mut chars)? {
+                            low @ 0xDC00..=0xDFFF => 0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00),
+                            _ => return None,
+                        }
+                    }
+                    code => code,
+                };
+                res.push(core::char::from_u32(code)?);
+            }
+            _ => return None,
+        }
+    }
+    Some(res)
+}
+// exactly 4 hex digits
+fn hex4(chars: &mut core::str::Chars) -> Option<u32> {
+    let mut code = 0;
+    for _ in 0..4 {
+        code = code * 16 + chars.next()?.to_digit(16)?;
+    }
+    Some(code)
+}
+
+// send lines produced by next over a channel from a new thread
+#[cfg(all(feature = "std", not(target_family = "wasm")))]
+fn line_channel<F>(mut next: F) -> Receiver<String>
+where
+    F: FnMut() -> Option<String> + Send + 'static,
+{
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        while let Some(line) = next() {
+            if tx.send(line).is_err() {
+                break;
+            }
+        }
+    });
+    rx
+}
 
 /*
     CIN
 */
 
 /// Stateless wrapper around stdin stream
+///
+/// A panic during a read, like in a `FromStr` implementation, doesn't break the stream for other code.
+/// Unfinished marks are dropped and reading continues after the consumed input
+///
+/// ``` rust
+///     use rcin::rin;
+///     use std::str::FromStr;
+///
+///     struct Fragile;
+///     impl FromStr for Fragile {
+///         type Err = ();
+///         fn from_str(_: &str) -> Result<Self, ()> {
+///             panic!("unexpected input")
+///         }
+///     }
+///
+///     rin.set_source(Box::new(&b"boom 42"[..]));
+///     assert!(std::panic::catch_unwind(|| rin.read::<Fragile>()).is_err());
+///     assert_eq!(rin.read::<i32>(), Some(42));
+/// ```
+///
+/// Reading from `rin` while a read is in progress on the same thread, like inside a `FromStr` implementation,
+/// panics instead of deadlocking. The `try_` methods return `ReadError::Busy` in that case
+///
+/// ``` rust
+///     use rcin::{rin, ReadError};
+///     use std::str::FromStr;
+///
+///     struct Nested(Result<i32, ReadError>);
+///     impl FromStr for Nested {
+///         type Err = ();
+///         fn from_str(_: &str) -> Result<Self, ()> {
+///             Ok(Nested(rin.try_read()))
+///         }
+///     }
+///
+///     rin.set_source(Box::new(&b"outer 1"[..]));
+///     assert_eq!(rin.read::<Nested>().unwrap().0, Err(ReadError::Busy));
+///     assert_eq!(rin.try_read::<i32>(), Ok(1));
+/// ```
+#[cfg(feature = "std")]
+#[derive(Copy, Clone)]
 pub struct RCin;
+#[cfg(feature = "std")]
 impl RCin {
-    /// Read value
-    pub fn read<T: FromStr>(&self) -> Option<T> {
-        let guard = GLOB_STREAM.lock().unwrap();
-        let mut rc: RefMut<Stream> = (*guard).borrow_mut();
+    /// Read value, a `char` is the next char after whitespace like `cin >> c`
+    pub fn read<T: FromStr + 'static>(&self) -> Option<T> {
+        let mut rc = lock_glob();
         rc.read()
     }
+    /// Read exactly N values into an array
+    pub fn read_array<T: FromStr + 'static, const N: usize>(&self) -> Option<[T; N]> {
+        let mut rc = lock_glob();
+        rc.read_array()
+    }
+    /// Fill slice with values, returns how many were read
+    pub fn read_into_slice<T: FromStr + 'static>(&self, slice: &mut [T]) -> usize {
+        let mut rc = lock_glob();
+        rc.read_into_slice(slice)
+    }
+    /// Read n and then n values
+    pub fn read_counted<T: FromStr + 'static>(&self) -> Option<Vec<T>> {
+        let mut rc = lock_glob();
+        rc.read_counted(Stream::read)
+    }
+    /// Read n and then n values into a HashSet
+    pub fn read_counted_set<T: FromStr + 'static + Hash + Eq>(&self) -> Option<HashSet<T>> {
+        let mut rc = lock_glob();
+        rc.read_counted(Stream::read)
+    }
+    /// Read n and then n values into a BTreeSet
+    pub fn read_counted_btree_set<T: FromStr + 'static + Ord>(&self) -> Option<BTreeSet<T>> {
+        let mut rc = lock_glob();
+        rc.read_counted(Stream::read)
+    }
+    /// Read n and then n key value pairs into a HashMap
+    pub fn read_counted_map<K: FromStr + 'static + Hash + Eq, V: FromStr + 'static>(&self) -> Option<HashMap<K, V>> {
+        let mut rc = lock_glob();
+        rc.read_counted(Stream::read_pair)
+    }
+    /// Read rows x cols values
+    pub fn read_matrix<T: FromStr + 'static>(&self, rows: usize, cols: usize) -> Option<Vec<Vec<T>>> {
+        let mut rc = lock_glob();
+        rc.read_matrix(rows, cols)
+    }
+    /// Read the next rows lines as grid of characters
+    pub fn read_char_grid(&self, rows: usize) -> Option<Vec<Vec<char>>> {
+        let mut rc = lock_glob();
+        rc.read_char_grid(rows)
+    }
+    /// Read value parsed by a manipulator, like `manip::Radix(16)`
+    pub fn read_with<T>(&self, manip: impl manip::Manipulator<T>) -> Option<T> {
+        let mut rc = lock_glob();
+        rc.read_with(manip)
+    }
+    /// Read token and parse it with f instead of `FromStr`
+    pub fn read_parsed<T>(&self, f: impl FnOnce(&str) -> Option<T>) -> Option<T> {
+        let mut rc = lock_glob();
+        rc.read_parsed(f)
+    }
+    /// Read enum value ignoring the case of the token
+    pub fn read_enum<T: FromStr + 'static>(&self) -> Option<T> {
+        let mut rc = lock_glob();
+        rc.read_enum()
+    }
+    /// Read token into the arena
+    #[cfg(feature = "bumpalo")]
+    pub fn read_in<'a>(&self, bump: &'a bumpalo::Bump) -> Option<&'a str> {
+        let mut rc = lock_glob();
+        rc.read_in(bump)
+    }
+    /// Read integer, clamped to the range of T if it doesn't fit
+    pub fn read_saturating<T: FastInt + 'static>(&self) -> Option<T> {
+        let mut rc = lock_glob();
+        rc.read_overflowing(true)
+    }
+    /// Read integer, wrapped around like with `wrapping_` arithmetic if it doesn't fit
+    pub fn read_wrapping<T: FastInt + 'static>(&self) -> Option<T> {
+        let mut rc = lock_glob();
+        rc.read_overflowing(false)
+    }
+    /// Read integer without any checks, see `RInStream::read_int_unchecked`
+    pub fn read_int_unchecked<T: FastInt + FromStr + 'static>(&self) -> Option<T> {
+        let mut rc = lock_glob();
+        rc.read_int_unchecked()
+    }
+    /// Read up to n integers straight from the byte buffer, see `RInStream::read_ints_bulk`
+    pub fn read_ints_bulk(&self, n: usize) -> Vec<i64> {
+        let mut rc = lock_glob();
+        rc.read_ints_bulk(n)
+    }
+    /// Read token as a symbol, repeated tokens don't allocate
+    pub fn read_interned(&self) -> Option<Symbol> {
+        let mut rc = lock_glob();
+        rc.read_interned()
+    }
+    /// Token of a symbol returned by `read_interned`, None for symbols this stream doesn't know
+    pub fn resolve(&self, sym: Symbol) -> Option<String> {
+        let rc = lock_glob();
+        rc.symbols.get(sym.0).cloned()
+    }
+    /// Read value and report why it failed
+    pub fn read_checked<T: FromStr + 'static>(&self) -> Result<T, ReadError> {
+        let mut rc = lock_glob();
+        rc.read_checked()
+    }
+    /// Read value that has to be within range
+    pub fn read_in_range<T: FromStr + 'static + PartialOrd, R: RangeBounds<T>>(&self, range: R) -> Result<T, ReadError> {
+        let mut rc = lock_glob();
+        rc.read_in_range(range)
+    }
+    /// Read value of type A or, if it doesn't parse, of type B
+    pub fn read_either<A: FromStr + 'static, B: FromStr + 'static>(&self) -> Option<Either<A, B>> {
+        let mut rc = lock_glob();
+        rc.read_either()
+    }
+    /// Read token and convert its escape sequences (`\n`, `\t`, `\uXXXX`, ...) into real characters
+    pub fn read_unescaped(&self) -> Option<String> {
+        let mut rc = lock_glob();
+        rc.read_unescaped()
+    }
+    /// Read values until EOF or the first one that can't be parsed
+    pub fn read_all<T: FromStr + 'static>(&self) -> Vec<T> {
+        let mut rc = lock_glob();
+        core::iter::from_fn(|| rc.read()).collect()
+    }
+    /// Read record up to the next `\0`, like the output of `find -print0`, line breaks are part of it
+    pub fn read_until_nul(&self) -> Option<String> {
+        let mut rc = lock_glob();
+        rc.read_until('\0')
+    }
+    /// Read all remaining `\0` separated records
+    pub fn read_all_nul_records(&self) -> Vec<String> {
+        let mut rc = lock_glob();
+        core::iter::from_fn(|| rc.read_until('\0')).collect()
+    }
+    /// Read all remaining lines
+    pub fn read_all_lines(&self) -> Vec<String> {
+        let mut rc = lock_glob();
+        core::iter::from_fn(|| rc.read_line()).collect()
+    }
+    /// Read lines while pred holds, the first line that fails it is not consumed
+    pub fn read_lines_while(&self, pred: impl FnMut(&str) -> bool) -> Vec<String> {
+        let mut rc = lock_glob();
+        rc.read_lines_while(pred)
+    }
+    /// Read a header line with the lines following it up to the next header
+    pub fn read_record(&self, is_header: impl FnMut(&str) -> bool) -> Option<Vec<String>> {
+        let mut rc = lock_glob();
+        rc.read_record(is_header)
+    }
+    /// Read consecutive lines indented by at least indent spaces and strip the indentation
+    pub fn read_indented_block(&self, indent: usize) -> Vec<String> {
+        let mut rc = lock_glob();
+        rc.read_indented_block(indent)
+    }
+    /// Read everything until EOF
+    pub fn read_to_string(&self) -> String {
+        let mut rc = lock_glob();
+        rc.read_to_string()
+    }
+    /// Read the next token without consuming it
+    pub fn peek_token(&self) -> Option<String> {
+        let mut rc = lock_glob();
+        rc.peek_token().map(str::to_string)
+    }
+    /// Read the next line without consuming it
+    pub fn peek_line(&self) -> Option<String> {
+        let mut rc = lock_glob();
+        rc.peek_line().map(str::to_string)
+    }
+    /// Next n chars without consuming them, at most 16 and fewer at the end of input
+    pub fn lookahead(&self, n: usize) -> Vec<char> {
+        let mut rc = lock_glob();
+        rc.lookahead(n).to_vec()
+    }
     /// Read line
     pub fn read_line(&self) -> Option<String> {
-        let guard = GLOB_STREAM.lock().unwrap();
-        let mut rc: RefMut<Stream> = (*guard).borrow_mut();
-        rc.read_line()
+        self.read_line_checked().ok()
+    }
+    /// Read line and report why it failed
+    pub fn read_line_checked(&self) -> Result<String, ReadError> {
+        let mut rc = lock_glob();
+        #[cfg(feature = "editor")]
+        if rc.line_editor && rc.editor_owns_input() {
+            return rc.edit_line().and_then(Result::ok).ok_or(ReadError::Eof);
+        }
+        rc.read_line_checked()
+    }
+    /// Read line, telling Ctrl-C apart from the end of input
+    ///
+    /// On a terminal the line is read in raw mode with the line editor, so Ctrl-C doesn't end the process.
+    /// Other sources only end with `Interrupt::Eof`
+    #[cfg(feature = "editor")]
+    pub fn read_line_interruptible(&self) -> Result<String, interactive::Interrupt> {
+        let mut rc = lock_glob();
+        if rc.editor_owns_input() {
+            return rc.edit_line().unwrap_or(Err(interactive::Interrupt::Eof));
+        }
+        rc.read_line().ok_or(interactive::Interrupt::Eof)
+    }
+    /// Print prompt and read a line echoing mask for every typed char, like for passwords
+    ///
+    /// Without a terminal the line is read as is
+    #[cfg(feature = "editor")]
+    pub fn read_masked(&self, prompt: &str, mask: char) -> Option<String> {
+        let mut rc = lock_glob();
+        if rc.editor_owns_input() {
+            return editor::read_masked(prompt, mask).ok()?.ok();
+        }
+        let mut out = io::stdout();
+        let _ = io::Write::write_all(&mut out, prompt.as_bytes());
+        let _ = io::Write::flush(&mut out);
+        // not recorded in the history
+        rc.read_until('\n')
+    }
+    /// Ask like `prompt_with_default` while counting down, the default is taken once the time is up
+    ///
+    /// The first key stops the countdown. Without a terminal it's the same as `prompt_with_default`
+    #[cfg(feature = "editor")]
+    pub fn prompt_timeout<T>(&self, prompt: &str, timeout: std::time::Duration, default: T) -> Result<T, ReadError>
+    where
+        T: FromStr + 'static + fmt::Display,
+    {
+        let mut rc = lock_glob();
+        if !rc.editor_owns_input() {
+            return rc.prompt_with_default(&mut io::stdout(), prompt.trim_end(), default);
+        }
+        let label = format!("{}[{}] ", prompt, default);
+        let line = editor::read_timeout(&label, timeout).ok().and_then(Result::ok).ok_or(ReadError::Eof)?;
+        let answer = line.trim();
+        if answer.is_empty() {
+            return Ok(default);
+        }
+        match rc.parse(answer) {
+            Some(value) => Ok(value),
+            None => Err(ReadError::Parse(answer.to_string())),
+        }
+    }
+    /// Edit lines read from a terminal with arrow keys, home/end and up/down for the history
+    ///
+    /// Only applies while rin reads stdin, it's a terminal and nothing is buffered
+    #[cfg(feature = "editor")]
+    pub fn set_line_editor(&self, enabled: bool) {
+        let mut rc = lock_glob();
+        rc.line_editor = enabled;
+    }
+    /// Complete the text before the cursor on tab in the line editor
+    ///
+    /// A single candidate replaces the text, several extend it to their common prefix or get listed
+    #[cfg(feature = "editor")]
+    pub fn set_completer(&self, completer: impl Fn(&str) -> Vec<String> + Send + 'static) {
+        let mut rc = lock_glob();
+        rc.completer = Some(Box::new(completer));
+    }
+    /// Record lines read by `read_line` in a history, None stops recording
+    pub fn set_history(&self, history: Option<interactive::History>) {
+        let mut rc = lock_glob();
+        rc.history = history;
+    }
+    /// Copy of the lines recorded since `set_history`
+    pub fn history(&self) -> Option<interactive::History> {
+        let rc = lock_glob();
+        rc.history.clone()
+    }
+    /// Print prompt to stdout, flush it and read value, used by `ask!`
+    pub fn ask<T: FromStr + 'static>(&self, prompt: fmt::Arguments) -> Option<T> {
+        let mut rc = lock_glob();
+        let mut out = io::stdout();
+        let _ = io::Write::write_fmt(&mut out, prompt);
+        let _ = io::Write::flush(&mut out);
+        rc.read()
+    }
+    /// Print `label [default]: ` to stdout and read a line, an empty one takes the default
+    pub fn prompt_with_default<T: FromStr + 'static + fmt::Display>(&self, label: &str, default: T) -> Result<T, ReadError> {
+        let mut rc = lock_glob();
+        rc.prompt_with_default(&mut io::stdout(), label, default)
+    }
+    /// Read value without waiting for the stream, fails with `ReadError::Busy` if it's in use
+    pub fn try_read<T: FromStr + 'static>(&self) -> Result<T, ReadError> {
+        let mut rc = try_lock_glob()?;
+        rc.read_checked()
+    }
+    /// Read line without waiting for the stream, fails with `ReadError::Busy` if it's in use
+    pub fn try_read_line(&self) -> Result<String, ReadError> {
+        let mut rc = try_lock_glob()?;
+        rc.read_line_checked()
+    }
+    /// Grow the buffer up to max bytes while reads fill it and shrink it back when they don't, None keeps its size
+    pub fn set_adaptive_buffer(&self, max: Option<usize>) {
+        let mut rc = lock_glob();
+        rc.set_adaptive_buffer(max)
+    }
+    /// Limit the length of tokens in chars, longer ones are skipped and fail with `ReadError::TokenTooLong`
+    pub fn set_max_token_len(&self, max: Option<usize>) {
+        let mut rc = lock_glob();
+        rc.max_token_len = max;
+    }
+    /// Limit the length of lines in chars, longer ones are skipped and fail with `ReadError::LineTooLong`
+    pub fn set_max_line_len(&self, max: Option<usize>) {
+        let mut rc = lock_glob();
+        rc.max_line_len = max;
+    }
+    /// Read a single CSV record, quoted fields can contain delimiters and newlines
+    pub fn read_csv_record(&self, delimiter: char) -> Option<Vec<String>> {
+        let mut rc = lock_glob();
+        rc.read_csv_record(delimiter)
+    }
+    /// Read a single CSV record and report why it failed, see `RInStream::read_csv_record_checked`
+    pub fn read_csv_record_checked(&self, delimiter: char) -> Result<Vec<String>, ReadError> {
+        let mut rc = lock_glob();
+        rc.read_csv_record_checked(delimiter)
+    }
+    /// Read a CSV header, later records can be read by column name
+    pub fn read_header(&self, delimiter: char) -> Option<Vec<String>> {
+        let mut rc = lock_glob();
+        rc.read_header(delimiter)
+    }
+    /// Read a CSV record as a map from header column to value
+    pub fn read_record_map(&self) -> Option<HashMap<String, String>> {
+        let mut rc = lock_glob();
+        rc.read_record_map()
+    }
+    /// Read a line and deserialize it from json, blank lines are skipped
+    #[cfg(feature = "serde_json")]
+    pub fn read_json<T: serde::de::DeserializeOwned>(&self) -> Option<T> {
+        let mut rc = lock_glob();
+        rc.read_json()
+    }
+    /// Read the next match of regex, skipping everything before it
+    #[cfg(feature = "regex")]
+    pub fn read_match(&self, regex: &regex::Regex) -> Option<String> {
+        let mut rc = lock_glob();
+        rc.read_match(regex)
+    }
+    /// Skip everything before the next match of regex, returns false if there is none
+    #[cfg(feature = "regex")]
+    pub fn skip_until_match(&self, regex: &regex::Regex) -> bool {
+        let mut rc = lock_glob();
+        rc.skip_until_match(regex)
+    }
+    /// Run a nom streaming parser, more lines are read while it returns `Incomplete`
+    #[cfg(feature = "nom")]
+    pub fn parse_nom<O>(&self, parser: impl FnMut(&str) -> nom::IResult<&str, O>) -> Result<O, ReadError> {
+        let mut rc = lock_glob();
+        rc.parse_nom(parser)
+    }
+    /// Read the next grapheme cluster (user-perceived character)
+    #[cfg(feature = "unicode-segmentation")]
+    pub fn read_grapheme(&self) -> Option<String> {
+        let mut rc = lock_glob();
+        rc.read_grapheme()
+    }
+    /// Skip all chars until needle, returns false if it wasn't found
+    pub fn skip_until(&self, needle: char, include: bool) -> bool {
+        let mut rc = lock_glob();
+        rc.skip_until(needle, include)
+    }
+    /// Skip all chars until needle string, returns false if it wasn't found
+    pub fn skip_until_str(&self, needle: &str, include: bool) -> bool {
+        let mut rc = lock_glob();
+        rc.skip_until_str(needle, include)
     }
     /// Skip all chars until next newline
     pub fn skip_line(&self) {
-        let guard = GLOB_STREAM.lock().unwrap();
-        let mut rc: RefMut<Stream> = (*guard).borrow_mut();
+        let mut rc = lock_glob();
         rc.skip_line()
     }
     /// Read the next character (can be whitespace)
     pub fn read_char(&self) -> Option<char>{
-        let guard = GLOB_STREAM.lock().unwrap();
-        let mut rc: RefMut<Stream> = (*guard).borrow_mut();
+        let mut rc = lock_glob();
         rc.pop_char()
     }
+    /// Read value with the span of its token in the input
+    pub fn read_spanned<T: FromStr + 'static>(&self) -> Result<(T, Span), ReadError> {
+        let mut rc = lock_glob();
+        rc.read_spanned()
+    }
+    /// Read value that has to be the whole token, `1GARBAGE` fails with `ReadError::TrailingGarbage`
+    pub fn read_strict<T: FromStr + 'static>(&self) -> Result<T, ReadError> {
+        let mut rc = lock_glob();
+        rc.read_strict()
+    }
+    /// Read one line and parse all of its fields, fails if there are too few or too many
+    pub fn parse_line<T: FromLine>(&self) -> Option<T> {
+        let mut rc = lock_glob();
+        rc.parse_line()
+    }
+    /// Read token in lowercase, all of unicode is folded
+    pub fn read_lowercase(&self) -> Option<String> {
+        let mut rc = lock_glob();
+        rc.read_lowercase()
+    }
+    /// Lowercase every token read, for matching commands and keywords in any case
+    pub fn set_fold_case(&self, enabled: bool) {
+        let mut rc = lock_glob();
+        rc.fold_case = enabled;
+    }
+    /// Read token of at most max chars, the rest of a longer one is read next
+    pub fn read_word(&self, max: usize) -> Option<String> {
+        let mut rc = lock_glob();
+        rc.read_word(max)
+    }
+    /// Read exactly n chars, whitespace included, nothing is consumed if there are fewer
+    pub fn read_chars(&self, n: usize) -> Option<String> {
+        let mut rc = lock_glob();
+        rc.read_chars(n)
+    }
+    /// Read a key, a terminal stdin is switched to raw mode with bracketed paste for the read with the editor feature
+    pub fn read_key_event(&self) -> Option<KeyEvent> {
+        let mut rc = lock_glob();
+        #[cfg(feature = "editor")]
+        let _raw = if rc.on_stdin && io::IsTerminal::is_terminal(&io::stdin()) {
+            Some(editor::RawMode::with_paste().ok()?)
+        } else {
+            None
+        };
+        rc.read_key_event()
+    }
+    /// Read the next character, Ok(None) is the end of input
+    pub fn next_char_result(&self) -> Result<Option<char>, DecodeError> {
+        let mut rc = lock_glob();
+        rc.next_char_result()
+    }
+}
+#[cfg(feature = "std")]
+impl RCin {
+    /// Lock the global stream for a sequence of reads, nested use of `rin` on this thread panics until it's dropped
+    pub fn lock(&self) -> RCinLock {
+        RCinLock { guard: lock_glob() }
+    }
+    /// Position of the next char
+    pub fn position(&self) -> Position {
+        let rc = lock_glob();
+        rc.pos
+    }
+    /// Counters collected since the stream was created
+    pub fn stats(&self) -> Stats {
+        let rc = lock_glob();
+        rc.stats
+    }
+    /// Hexdump with ascii of the next n buffered bytes without consuming them
+    pub fn debug_peek(&self, n: usize) -> String {
+        let mut rc = lock_glob();
+        rc.debug_peek(n)
+    }
+    /// Token, type and position of the most recent read that failed, like `rin >> &mut x` returning false
+    pub fn last_failure(&self) -> Option<ReadFailure> {
+        let rc = lock_glob();
+        rc.last_failure.clone()
+    }
+    /// Set the chars that separate tokens
+    pub fn set_whitespace(&self, mode: WhitespaceMode) {
+        let mut rc = lock_glob();
+        rc.whitespace = mode;
+    }
+    /// Kind of line breaks read so far
+    pub fn detect_line_ending(&self) -> LineEnding {
+        let rc = lock_glob();
+        rc.line_ending()
+    }
+    /// Read `\r\n` as `\n` in all reads
+    pub fn set_strip_cr(&self, enabled: bool) {
+        let mut rc = lock_glob();
+        rc.strip_cr = enabled;
+    }
+    /// Join lines ending in a backslash with the next line in all reads
+    pub fn set_line_continuation(&self, enabled: bool) {
+        let mut rc = lock_glob();
+        rc.continuation = enabled;
+    }
+    /// Parse T with f instead of `FromStr` in all reads of the stream
+    pub fn register_parser<T: 'static>(&self, f: impl Fn(&str) -> Option<T> + Send + 'static) {
+        let mut rc = lock_glob();
+        rc.register_parser(f);
+    }
+    /// Choose what reads and `rin >> &mut x` do with tokens that don't parse
+    pub fn set_parse_policy(&self, policy: ParsePolicy) {
+        let mut rc = lock_glob();
+        rc.policy = policy;
+    }
+    /// Let `ParsePolicy::UseDefault` substitute `T::default()`
+    pub fn register_default<T: Default + 'static>(&self) {
+        let mut rc = lock_glob();
+        rc.register_default::<T>();
+    }
+    /// Normalize tokens and lines to NFC, chars read one by one stay as they are
+    #[cfg(feature = "unicode-normalization")]
+    pub fn set_normalize_nfc(&self, enabled: bool) {
+        let mut rc = lock_glob();
+        rc.nfc = enabled;
+    }
+    /// Print every token and line read to stderr with its position, also enabled by setting `RCIN_TRACE`
+    pub fn set_trace(&self, enabled: bool) {
+        let mut rc = lock_glob();
+        rc.trace = enabled;
+    }
+    /// Call f on every failed read and invalid utf8 in the input, f must not use rin
+    pub fn set_error_handler(&self, f: impl FnMut(&ReadFailure) + Send + 'static) {
+        let mut rc = lock_glob();
+        rc.error_handler = Some(Box::new(f));
+    }
+    /// Toggle skipping whitespace around tokens, like c++ skipws/noskipws
+    pub fn skipws(&self, skip: bool) {
+        let mut rc = lock_glob();
+        rc.skipws = skip;
+    }
+    /// Read stdin lines on a background thread and send them over a channel
+    #[cfg(not(target_family = "wasm"))]
+    pub fn line_channel(&self) -> Receiver<String> {
+        line_channel(move || rin.read_line())
+    }
+    /// Replace stdin as the source of the global stream, buffered input is dropped
+    ///
+    /// Useful where there is no real stdin, like a browser page feeding input from javascript
+    pub fn set_source(&self, src: Box<dyn Read + Send>) {
+        let mut rc = lock_glob();
+        #[cfg(feature = "editor")]
+        {
+            rc.on_stdin = false;
+        }
+        rc.reset(Box::new(Plain(src)));
+    }
+    /// Drop ansi escape sequences like colors and cursor movement from the bytes read from now on
+    pub fn strip_ansi(&self) {
+        let mut rc = lock_glob();
+        let inner = core::mem::replace(&mut rc.source, Box::new(&[][..]));
+        rc.source = Box::new(AnsiStripped {
+            inner,
+            state: AnsiState::Text,
+        });
+    }
+    /// Read the arguments of the process after the program name instead of stdin if there are any
+    pub fn prefer_args(&self) {
+        let args: Vec<String> = std::env::args().skip(1).collect();
+        if !args.is_empty() {
+            self.set_source(Box::new(io::Cursor::new(join_args(args))));
+        }
+    }
+    /// Continue reading from the terminal (`/dev/tty`, `CONIN$`) once piped stdin ends
+    ///
+    /// Allows asking the user questions after consuming data from a pipe
+    pub fn enable_tty_fallback(&self) {
+        let mut rc = lock_glob();
+        let inner = std::mem::replace(&mut rc.source, Box::new(&[][..]));
+        rc.source = Box::new(TtyFallback {
+            inner,
+            switched: false,
+        });
+    }
+    /// Start recording consumed input, so it can be rolled back
+    pub fn mark(&self) {
+        let mut rc = lock_glob();
+        rc.mark()
+    }
+    /// Return all input consumed since the last mark back to the stream
+    pub fn rollback(&self) {
+        let mut rc = lock_glob();
+        rc.rollback()
+    }
+    /// Drop the last mark keeping the consumed input
+    pub fn commit(&self) {
+        let mut rc = lock_glob();
+        rc.commit()
+    }
+    /// Run f and roll back all input it consumed if it returns None
+    pub fn attempt<T>(&self, f: impl FnOnce(&RCin) -> Option<T>) -> Option<T> {
+        self.mark();
+        let res = f(self);
+        if res.is_some() {
+            self.commit();
+        } else {
+            self.rollback();
+        }
+        res
+    }
 }
-impl<T> std::ops::Shr<&mut T> for rin
+#[cfg(feature = "std")]
+impl<T> std::ops::Shr<&mut T> for RCin
 where
-    T: FromStr,
+    T: FromStr + 'static,
 {
     type Output = bool;
     fn shr(self, rhs: &mut T) -> Self::Output {
@@ -254,12 +3467,117 @@ where
     }
 }
 
-lazy_static! {
-    /// Global stdin stream instance
-    #[derive(Copy, Clone)]
-    pub static ref rin: RCin = RCin{};
-    static ref GLOB_STREAM: Mutex<RefCell<Stream>> =
-        Mutex::new(RefCell::new(Stream::new(Box::new(stdin()),DEFAULT_BUF_SIZE)));
+/// Global stdin stream instance
+#[cfg(feature = "std")]
+#[allow(non_upper_case_globals)]
+pub static rin: RCin = RCin;
+
+// stdin, a windows console is read as utf16 and converted
+#[cfg(feature = "std")]
+fn stdin_source() -> Box<dyn Read + Send> {
+    #[cfg(windows)]
+    if let Some(console) = console::Console::stdin() {
+        return Box::new(console);
+    }
+    Box::new(stdin())
+}
+// the stdin stream behind rin, created on first use
+#[cfg(feature = "std")]
+fn glob_stream() -> &'static Mutex<Stream> {
+    static GLOB_STREAM: OnceLock<Mutex<Stream>> = OnceLock::new();
+    GLOB_STREAM.get_or_init(|| {
+        #[allow(unused_mut)]
+        let mut stream = Stream::new(Box::new(Plain(stdin_source())), DEFAULT_BUF_SIZE);
+        #[cfg(feature = "editor")]
+        {
+            stream.on_stdin = true;
+        }
+        Mutex::new(stream)
+    })
+}
+#[cfg(feature = "std")]
+thread_local! {
+    // set while this thread holds the global stream
+    static HOLDING: Cell<bool> = const { Cell::new(false) };
+}
+// guard of the global stream that tracks the holding thread
+#[cfg(feature = "std")]
+struct GlobGuard(MutexGuard<'static, Stream>);
+#[cfg(feature = "std")]
+impl GlobGuard {
+    fn new(guard: MutexGuard<'static, Stream>) -> Self {
+        HOLDING.with(|h| h.set(true));
+        GlobGuard(guard)
+    }
+}
+#[cfg(feature = "std")]
+impl core::ops::Deref for GlobGuard {
+    type Target = Stream;
+    fn deref(&self) -> &Stream {
+        &self.0
+    }
+}
+#[cfg(feature = "std")]
+impl core::ops::DerefMut for GlobGuard {
+    fn deref_mut(&mut self) -> &mut Stream {
+        &mut self.0
+    }
+}
+#[cfg(feature = "std")]
+impl Drop for GlobGuard {
+    fn drop(&mut self) {
+        HOLDING.with(|h| h.set(false));
+    }
+}
+// a panic while holding the lock leaves the stream poisoned
+#[cfg(feature = "std")]
+fn recover_glob(poisoned: std::sync::PoisonError<MutexGuard<'static, Stream>>) -> MutexGuard<'static, Stream> {
+    glob_stream().clear_poison();
+    let mut rc = poisoned.into_inner();
+    rc.recover();
+    rc
+}
+// lock the global stream, a nested use on the same thread would deadlock and panics instead
+#[cfg(feature = "std")]
+fn lock_glob() -> GlobGuard {
+    if HOLDING.with(Cell::get) {
+        panic!("rin used while already reading on this thread, use the try_ methods from nested code");
+    }
+    GlobGuard::new(glob_stream().lock().unwrap_or_else(recover_glob))
+}
+// lock a stream shared between threads, recovering it like the global one
+#[cfg(feature = "std")]
+fn lock_stream(stream: &Mutex<RInStream>) -> MutexGuard<'_, RInStream> {
+    stream.lock().unwrap_or_else(|poisoned| {
+        stream.clear_poison();
+        let mut guard = poisoned.into_inner();
+        guard.source.recover();
+        guard
+    })
+}
+// arguments as the input of a stream, separated by spaces
+#[cfg(feature = "std")]
+fn join_args(args: impl IntoIterator<Item = String>) -> Vec<u8> {
+    let mut text = String::new();
+    for arg in args {
+        if !text.is_empty() {
+            text.push(' ');
+        }
+        text.push_str(&arg);
+    }
+    text.into_bytes()
+}
+// lock the global stream without waiting
+#[cfg(feature = "std")]
+fn try_lock_glob() -> Result<GlobGuard, ReadError> {
+    if HOLDING.with(Cell::get) {
+        return Err(ReadError::Busy);
+    }
+    match glob_stream().try_lock() {
+        Ok(guard) => Ok(GlobGuard::new(guard)),
+        Err(TryLockError::Poisoned(poisoned)) => Ok(GlobGuard::new(recover_glob(poisoned))),
+        Err(TryLockError::WouldBlock) => Err(ReadError::Busy),
+    }
 }
 
 /*
@@ -271,38 +3589,1873 @@ pub struct RInStream {
 }
 impl RInStream {
     /// Create new stream from file
+    #[cfg(feature = "std")]
     pub fn from_file(f: File) -> Self {
-        Self::from_source(Box::new(f))
+        let total_size = f.metadata().ok().map(|m| m.len());
+        #[cfg(any(unix, windows))]
+        let handle = multi::handle_of(&f);
+        let mut stream = Self::from_seekable(f);
+        stream.source.total_size = total_size;
+        #[cfg(any(unix, windows))]
+        {
+            stream.source.wait_handle = Some(handle);
+        }
+        stream
+    }
+    /// Open a file and convert it to utf8 from the encoding given by its byte order mark or guessed from its bytes
+    ///
+    /// Utf16 is recognized by a byte order mark or by the zero bytes of ascii text, input that isn't valid utf8 is latin1
+    ///
+    /// ``` rust
+    ///     use rcin::RInStream;
+    ///
+    ///     let path = std::env::temp_dir().join("rcin_autodetect_doc.txt");
+    ///     let utf16: Vec<u8> = "\u{feff}größe 3".encode_utf16().flat_map(u16::to_le_bytes).collect();
+    ///     std::fs::write(&path, utf16).unwrap();
+    ///     let mut reader = RInStream::from_path_autodetect(&path).unwrap();
+    ///     assert_eq!(reader.read::<String>(), Some("größe".to_string()));
+    ///     assert_eq!(reader.read::<i32>(), Some(3));
+    ///
+    ///     std::fs::write(&path, b"caf\xe9\n").unwrap();
+    ///     let mut reader = RInStream::from_path_autodetect(&path).unwrap();
+    ///     assert_eq!(reader.read_line(), Some("café".to_string()));
+    /// ```
+    #[cfg(feature = "encoding")]
+    pub fn from_path_autodetect(path: impl AsRef<std::path::Path>) -> io::Result<Self> {
+        let mut file = File::open(path)?;
+        let mut sample = Vec::new();
+        (&mut file).take(4096).read_to_end(&mut sample)?;
+        let (encoding, bom) = sniff_encoding(&sample).unwrap_or_else(|| {
+            // a char cut by the end of the sample is fine
+            let valid = match core::str::from_utf8(&sample) {
+                Ok(_) => true,
+                Err(e) => e.error_len().is_none() && sample.len() == 4096,
+            };
+            (if valid { Encoding::Utf8 } else { Encoding::Latin1 }, 0)
+        });
+        file.seek(SeekFrom::Start(bom as u64))?;
+        let source = Transcoded {
+            inner: file,
+            encoding,
+            carry: Vec::new(),
+            out: Vec::new(),
+            start: 0,
+        };
+        Ok(Self::from_byte_source(source, DEFAULT_BUF_SIZE))
+    }
+    /// Create new stream from file read with io_uring, the next chunk is read while the current one is decoded
+    ///
+    /// Fails if the kernel doesn't support io_uring
+    ///
+    /// ``` rust
+    ///     use rcin::RInStream;
+    ///     use std::fs::File;
+    ///
+    ///     let path = std::env::temp_dir().join("rcin_uring_doc.txt");
+    ///     std::fs::write(&path, "1 2 3\nend").unwrap();
+    ///     if let Ok(mut reader) = RInStream::from_file_uring(File::open(&path).unwrap()) {
+    ///         assert_eq!(reader.read::<i32>(), Some(1));
+    ///         assert_eq!(reader.read_line().as_deref(), Some("2 3"));
+    ///         assert_eq!(reader.read_line().as_deref(), Some("end"));
+    ///     }
+    /// ```
+    ///
+    /// Reads and seeks work across the chunks of a large file
+    ///
+    /// ``` rust
+    ///     use rcin::RInStream;
+    ///     use std::fs::File;
+    ///     use std::io::SeekFrom;
+    ///
+    ///     let path = std::env::temp_dir().join("rcin_uring_big_doc.txt");
+    ///     let text: String = (0..100_000u64).map(|i| format!("{:06}\n", i)).collect();
+    ///     std::fs::write(&path, &text).unwrap();
+    ///     if let Ok(mut reader) = RInStream::from_file_uring(File::open(&path).unwrap()) {
+    ///         assert_eq!(reader.read_all::<u64>().iter().sum::<u64>(), (0..100_000u64).sum::<u64>());
+    ///         reader.seek(SeekFrom::Start(7 * 12_345)).unwrap();
+    ///         assert_eq!(reader.read::<u64>(), Some(12_345));
+    ///         reader.seek(SeekFrom::Start(7 * 99_999)).unwrap();
+    ///         assert_eq!(reader.read::<u64>(), Some(99_999));
+    ///         assert_eq!(reader.read::<u64>(), None);
+    ///         reader.seek(SeekFrom::Start(0)).unwrap();
+    ///         assert_eq!(reader.read::<u64>(), Some(0));
+    ///     }
+    /// ```
+    #[cfg(all(feature = "io-uring", target_os = "linux"))]
+    pub fn from_file_uring(mut f: File) -> io::Result<Self> {
+        let start = f.stream_position()?;
+        let total_size = f.metadata().ok().map(|m| m.len());
+        let source = uring::UringFile::new(f, start)?;
+        let mut stream = RInStream {
+            source: Stream::new(Box::new(source), DEFAULT_BUF_SIZE),
+        };
+        stream.source.consumed = start as usize;
+        stream.source.pos.offset = start as usize;
+        stream.source.total_size = total_size;
+        Ok(stream)
+    }
+    /// Create new stream from a source that supports `seek`
+    #[cfg(feature = "std")]
+    pub fn from_seekable<R: Read + Seek + Send + 'static>(mut src: R) -> Self {
+        let start = src.stream_position().unwrap_or(0) as usize;
+        let mut stream = RInStream {
+            source: Stream::new(Box::new(Seekable(src)), DEFAULT_BUF_SIZE),
+        };
+        stream.source.consumed = start;
+        stream.source.pos.offset = start;
+        stream
     }
     /// Create new stream from source
+    #[cfg(feature = "std")]
     pub fn from_source(src: Box<dyn Read + Send>) -> Self {
         Self::new(src, DEFAULT_BUF_SIZE)
     }
+    /// Create new stream reading the arguments joined by spaces, like `cmd 3 4` read as the line `3 4`
+    ///
+    /// An argument containing whitespace is split into several tokens
+    ///
+    /// ``` rust
+    ///     use rcin::RInStream;
+    ///
+    ///     let args = vec!["3".to_string(), "hello world".to_string(), "7".to_string()];
+    ///     let mut reader = RInStream::from_args(args);
+    ///     assert_eq!(reader.read::<i32>(), Some(3));
+    ///     assert_eq!(reader.read::<String>().as_deref(), Some("hello"));
+    ///     assert_eq!(reader.read_line().as_deref(), Some("world 7"));
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Self {
+        Self::from_source(Box::new(io::Cursor::new(join_args(args))))
+    }
+    /// Read the arguments of the process after the program name if there are any, stdin otherwise
+    #[cfg(feature = "std")]
+    pub fn from_args_or_stdin() -> Self {
+        let args: Vec<String> = std::env::args().skip(1).collect();
+        if args.is_empty() {
+            Self::from_source(stdin_source())
+        } else {
+            Self::from_args(args)
+        }
+    }
     /// Create new stream from source with given buffer size in bytes
+    #[cfg(feature = "std")]
     pub fn new(src: Box<dyn Read + Send>, cap: usize) -> Self {
         RInStream {
-            source: Stream::new(src, cap),
+            source: Stream::new(Box::new(Plain(src)), cap),
+        }
+    }
+    /// Continue with a new source, the buffer and configuration are reused
+    ///
+    /// All buffered input, marks, the csv header, position and stats are cleared
+    ///
+    /// ``` rust
+    ///     use rcin::RInStream;
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new(&b"1 2"[..]));
+    ///     reader.read::<i32>();
+    ///     reader.reset(Box::new(&b"3"[..]));
+    ///     assert_eq!(reader.read_all::<i32>(), vec![3]);
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn reset(&mut self, src: Box<dyn Read + Send>) {
+        self.source.reset(Box::new(Plain(src)))
+    }
+    /// Reposition a file or other seekable source, `SeekFrom::Current` is relative to `stream_position`
+    ///
+    /// Fails if the stream wasn't created with `from_file` or `from_seekable`. Buffered input and
+    /// marks are dropped. Line and column are counted up to the target from the current position
+    /// when seeking forward, from the start of its line after `build_line_index`, otherwise from
+    /// the start of the source
+    ///
+    /// ``` rust
+    ///     use std::io::{Cursor, SeekFrom};
+    ///     use rcin::RInStream;
+    ///
+    ///     let mut reader = RInStream::from_seekable(Cursor::new("first second"));
+    ///     assert_eq!(reader.read::<String>(), Some("first".to_string()));
+    ///     assert_eq!(reader.stream_position(), 6);
+    ///     reader.seek(SeekFrom::Start(0)).unwrap();
+    ///     assert_eq!(reader.read::<String>(), Some("first".to_string()));
+    ///
+    ///     let mut reader = RInStream::from_seekable(Cursor::new("ab\ncdé f"));
+    ///     reader.seek(SeekFrom::End(-1)).unwrap();
+    ///     assert_eq!((reader.position().line, reader.position().column), (2, 5));
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.source.seek(pos)
+    }
+    /// Byte offset of the next unread char in the source
+    pub fn stream_position(&self) -> u64 {
+        self.source.pos.offset as u64
+    }
+    /// Scan the whole seekable source once to allow `goto_line`, the stream keeps its position
+    #[cfg(feature = "std")]
+    pub fn build_line_index(&mut self) -> io::Result<()> {
+        self.source.build_line_index()
+    }
+    /// Use an index built before, it has to match the source
+    #[cfg(feature = "std")]
+    pub fn set_line_index(&mut self, index: LineIndex) {
+        self.source.line_index = Some(index);
+    }
+    /// Jump to the start of the line, lines start at 1
+    ///
+    /// ``` rust
+    ///     use std::io::Cursor;
+    ///     use rcin::RInStream;
+    ///
+    ///     let mut reader = RInStream::from_seekable(Cursor::new("a\nb\nc\n"));
+    ///     reader.build_line_index().unwrap();
+    ///     reader.goto_line(3).unwrap();
+    ///     assert_eq!(reader.read_line(), Some("c".to_string()));
+    ///     reader.goto_line(2).unwrap();
+    ///     assert_eq!(reader.read_line(), Some("b".to_string()));
+    ///     assert!(reader.goto_line(4).is_err());
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn goto_line(&mut self, line: usize) -> io::Result<()> {
+        self.source.goto_line(line)
+    }
+    /// Create new stream from a byte source with given buffer size in bytes, works without std
+    ///
+    /// ``` rust
+    ///     use rcin::RInStream;
+    ///
+    ///     let mut reader = RInStream::from_byte_source(&b"42"[..], 64);
+    ///     assert_eq!(reader.read::<u8>(), Some(42));
+    /// ```
+    pub fn from_byte_source(src: impl ByteSource + 'static, cap: usize) -> Self {
+        RInStream {
+            source: Stream::new(Box::new(src), cap),
         }
     }
+    /// Stop reading from the source after max_bytes in total, guards against unbounded input
+    ///
+    /// ``` rust
+    ///     use rcin::RInStream;
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new(&b"12345678"[..])).with_limit(4);
+    ///     assert_eq!(reader.read::<i32>(), Some(1234));
+    ///     assert_eq!(reader.read::<i32>(), None);
+    /// ```
+    pub fn with_limit(mut self, max_bytes: u64) -> Self {
+        let remaining = max_bytes.saturating_sub(self.source.stats.bytes_read);
+        let inner = core::mem::replace(&mut self.source.source, Box::new(&[][..]));
+        self.source.source = Box::new(Limited {
+            inner,
+            left: remaining,
+        });
+        self
+    }
+    /// Transform every chunk of bytes read from the source from now on before it's decoded
+    ///
+    /// f can change the length of a chunk, like to drop bytes or to decrypt
+    ///
+    /// ``` rust
+    ///     use rcin::RInStream;
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new(&b"\x1f\x1a\x13\x12"[..])).map_bytes(|chunk| {
+    ///         chunk.iter_mut().for_each(|b| *b ^= 0x2a);
+    ///     });
+    ///     assert_eq!(reader.read::<i32>(), Some(5098));
+    /// ```
+    pub fn map_bytes(mut self, f: impl FnMut(&mut Vec<u8>) + Send + 'static) -> Self {
+        let inner = core::mem::replace(&mut self.source.source, Box::new(&[][..]));
+        self.source.source = Box::new(Mapped {
+            inner,
+            map: Box::new(f),
+            chunk: Vec::new(),
+            ptr: 0,
+        });
+        self
+    }
+    /// Drop ansi escape sequences like colors and cursor movement from the bytes read from now on
+    ///
+    /// ``` rust
+    ///     use rcin::RInStream;
+    ///
+    ///     let colored = b"\x1b[1;32mok\x1b[0m 42\x1b]0;title\x07\n";
+    ///     let mut reader = RInStream::from_source(Box::new(&colored[..])).strip_ansi();
+    ///     assert_eq!(reader.read_line().as_deref(), Some("ok 42"));
+    /// ```
+    pub fn strip_ansi(mut self) -> Self {
+        let inner = core::mem::replace(&mut self.source.source, Box::new(&[][..]));
+        self.source.source = Box::new(AnsiStripped {
+            inner,
+            state: AnsiState::Text,
+        });
+        self
+    }
+    /// Hash every byte consumed from now on with the given digest
+    ///
+    /// ``` rust
+    ///     use rcin::RInStream;
+    ///     use sha2::{Digest, Sha256};
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new(&b"abc"[..])).with_digest(Sha256::new());
+    ///     reader.read_to_string();
+    ///     assert_eq!(reader.finalize_digest().unwrap()[..], Sha256::digest(b"abc")[..]);
+    /// ```
+    #[cfg(feature = "digest")]
+    pub fn with_digest(mut self, digest: impl digest::DynDigest + Send + 'static) -> Self {
+        self.source.digest = Some((Box::new(digest), self.source.ptr));
+        self
+    }
+    /// Hash of all bytes consumed since `with_digest`, None if there is no digest
+    #[cfg(feature = "digest")]
+    pub fn finalize_digest(&mut self) -> Option<Box<[u8]>> {
+        self.source.update_digest();
+        let (hasher, _) = self.source.digest.as_ref()?;
+        Some(hasher.box_clone().finalize())
+    }
     /// Read the next character (can be whitespace)
+    ///
+    /// ``` rust
+    ///     use rcin::RInStream;
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new("aé€😀".as_bytes()));
+    ///     let chars: Vec<char> = std::iter::from_fn(|| reader.read_char()).collect();
+    ///     assert_eq!(chars, vec!['a', 'é', '€', '😀']);
+    /// ```
     pub fn read_char(&mut self) -> Option<char>{
         self.source.pop_char()
     }
+    /// Read value with the span of its token in the input
+    ///
+    /// ``` rust
+    ///     use rcin::{RInStream, Span};
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new(&b"key =\n  1024"[..]));
+    ///     reader.read::<String>();
+    ///     reader.read::<char>();
+    ///     let (value, span) = reader.read_spanned::<u32>().unwrap();
+    ///     assert_eq!(value, 1024);
+    ///     assert_eq!(span, Span { byte_start: 8, byte_end: 12, line: 2, col: 3 });
+    ///
+    ///     // the span covers the input, not the folded token
+    ///     let mut reader = RInStream::from_source(Box::new("İ x".as_bytes()));
+    ///     reader.set_fold_case(true);
+    ///     let (token, span) = reader.read_spanned::<String>().unwrap();
+    ///     assert_eq!((token.len(), span.len()), (3, 2));
+    /// ```
+    pub fn read_spanned<T: FromStr + 'static>(&mut self) -> Result<(T, Span), ReadError> {
+        self.source.read_spanned()
+    }
+    /// Read value that has to be the whole token, `1GARBAGE` fails with `ReadError::TrailingGarbage`
+    ///
+    /// A `char` has to be a token of its own, the rest of a longer token is consumed with the error
+    ///
+    /// ``` rust
+    ///     use rcin::{ReadError, RInStream};
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new(&b"1GARBAGE x yz 2"[..]));
+    ///     assert_eq!(reader.read_strict::<i32>(), Err(ReadError::TrailingGarbage("1GARBAGE".into())));
+    ///     assert_eq!(reader.read_strict::<char>(), Ok('x'));
+    ///     assert_eq!(reader.read_strict::<char>(), Err(ReadError::TrailingGarbage("yz".into())));
+    ///     assert_eq!(reader.read_strict::<i32>(), Ok(2));
+    /// ```
+    pub fn read_strict<T: FromStr + 'static>(&mut self) -> Result<T, ReadError> {
+        self.source.read_strict()
+    }
+    /// Read one line and parse all of its fields, fails if there are too few or too many
+    ///
+    /// ``` rust
+    ///     use rcin::RInStream;
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new(&b"alice 30 1.5\n1 2 3 4\nbob 40\n"[..]));
+    ///     assert_eq!(reader.parse_line::<(String, u32, f64)>(), Some(("alice".to_string(), 30, 1.5)));
+    ///     assert_eq!(reader.parse_line::<Vec<i32>>(), Some(vec![1, 2, 3, 4]));
+    ///     assert_eq!(reader.parse_line::<(String, u32, f64)>(), None);
+    /// ```
+    pub fn parse_line<T: FromLine>(&mut self) -> Option<T> {
+        self.source.parse_line()
+    }
+    /// Read token of at most max chars, the rest of a longer one is read next
+    ///
+    /// ``` rust
+    ///     use rcin::RInStream;
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new(&b"ABCDEFGH IJ"[..]));
+    ///     assert_eq!(reader.read_word(4).as_deref(), Some("ABCD"));
+    ///     assert_eq!(reader.read_word(4).as_deref(), Some("EFGH"));
+    ///     assert_eq!(reader.read_word(4).as_deref(), Some("IJ"));
+    /// ```
+    pub fn read_word(&mut self, max: usize) -> Option<String> {
+        self.source.read_word(max)
+    }
+    /// Read token in lowercase, all of unicode is folded
+    ///
+    /// ``` rust
+    ///     use rcin::RInStream;
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new("QUIT Straße ÉTÉ".as_bytes()));
+    ///     assert_eq!(reader.read_lowercase().as_deref(), Some("quit"));
+    ///     reader.set_fold_case(true);
+    ///     assert_eq!(reader.read::<String>().as_deref(), Some("straße"));
+    ///     assert_eq!(reader.peek_token(), Some("été"));
+    /// ```
+    pub fn read_lowercase(&mut self) -> Option<String> {
+        self.source.read_lowercase()
+    }
+    /// Lowercase every token read, for matching commands and keywords in any case
+    ///
+    /// Lines and chars read one by one keep their case
+    pub fn set_fold_case(&mut self, enabled: bool) {
+        self.source.fold_case = enabled;
+    }
+    /// Read exactly n chars, whitespace included, nothing is consumed if there are fewer
+    ///
+    /// ``` rust
+    ///     use rcin::RInStream;
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new(&b"#. .#\n.."[..]));
+    ///     assert_eq!(reader.read_chars(3).as_deref(), Some("#. "));
+    ///     assert_eq!(reader.read_chars(3).as_deref(), Some(".#\n"));
+    ///     assert_eq!(reader.read_chars(3), None);
+    ///     assert_eq!(reader.read_chars(2).as_deref(), Some(".."));
+    /// ```
+    pub fn read_chars(&mut self, n: usize) -> Option<String> {
+        self.source.read_chars(n)
+    }
+    /// Read a key sent by a terminal in raw mode, decoding escape sequences of special keys
+    ///
+    /// ``` rust
+    ///     use rcin::{Direction, KeyEvent, RInStream};
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new(&b"a\x03\x1b[A\x1b[3~\x1bOP\r\x1b[200~x\ny\x1b[201~"[..]));
+    ///     assert_eq!(reader.read_key_event(), Some(KeyEvent::Char('a')));
+    ///     assert_eq!(reader.read_key_event(), Some(KeyEvent::Ctrl('c')));
+    ///     assert_eq!(reader.read_key_event(), Some(KeyEvent::Arrow(Direction::Up)));
+    ///     assert_eq!(reader.read_key_event(), Some(KeyEvent::Delete));
+    ///     assert_eq!(reader.read_key_event(), Some(KeyEvent::F(1)));
+    ///     assert_eq!(reader.read_key_event(), Some(KeyEvent::Enter));
+    ///     assert_eq!(reader.read_key_event(), Some(KeyEvent::Paste("x\ny".into())));
+    /// ```
+    pub fn read_key_event(&mut self) -> Option<KeyEvent> {
+        self.source.read_key_event()
+    }
+    /// Read the next character, Ok(None) is the end of input
+    ///
+    /// Unlike `read_char` it reports why there is no char, reading can continue after invalid utf8
+    ///
+    /// ``` rust
+    ///     use rcin::{DecodeError, RInStream};
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new(&b"a\xffb"[..]));
+    ///     assert_eq!(reader.next_char_result(), Ok(Some('a')));
+    ///     assert!(matches!(reader.next_char_result(), Err(DecodeError::InvalidUtf8(at)) if at.offset == 1));
+    ///     assert_eq!(reader.next_char_result(), Ok(Some('b')));
+    ///     assert_eq!(reader.next_char_result(), Ok(None));
+    /// ```
+    pub fn next_char_result(&mut self) -> Result<Option<char>, DecodeError> {
+        self.source.next_char_result()
+    }
     /// Read value
-    pub fn read<T: FromStr>(&mut self) -> Option<T> {
+    ///
+    /// A `char` is the next char after whitespace, like `cin >> c`, the rest of the token stays in the stream
+    ///
+    /// ``` rust
+    ///     use rcin::RInStream;
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new(&b"  ab 7"[..]));
+    ///     assert_eq!(reader.read::<char>(), Some('a'));
+    ///     assert_eq!(reader.read::<char>(), Some('b'));
+    ///     assert_eq!(reader.read::<i32>(), Some(7));
+    /// ```
+    pub fn read<T: FromStr + 'static>(&mut self) -> Option<T> {
         self.source.read()
     }
+    /// Read exactly N values into an array
+    ///
+    /// ``` rust
+    ///     use rcin::RInStream;
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new(&b"1.5 2 -3"[..]));
+    ///     let [x, y, z] = reader.read_array::<f64, 3>().unwrap();
+    ///     assert_eq!(x + y + z, 0.5);
+    /// ```
+    pub fn read_array<T: FromStr + 'static, const N: usize>(&mut self) -> Option<[T; N]> {
+        self.source.read_array()
+    }
+    /// Fill slice with values, returns how many were read
+    ///
+    /// ``` rust
+    ///     use rcin::RInStream;
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new(&b"1 2 3 4 5"[..]));
+    ///     let mut buf = [0; 3];
+    ///     assert_eq!(reader.read_into_slice(&mut buf), 3);
+    ///     assert_eq!(reader.read_into_slice(&mut buf), 2);
+    ///     assert_eq!(buf, [4, 5, 3]);
+    /// ```
+    pub fn read_into_slice<T: FromStr + 'static>(&mut self, slice: &mut [T]) -> usize {
+        self.source.read_into_slice(slice)
+    }
+    /// Read n and then n values
+    ///
+    /// ``` rust
+    ///     use rcin::RInStream;
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new(&b"3 1 2 3\n2 a 1 b 2"[..]));
+    ///     assert_eq!(reader.read_counted::<i32>(), Some(vec![1, 2, 3]));
+    ///     let map = reader.read_counted_map::<String, i32>().unwrap();
+    ///     assert_eq!(map["b"], 2);
+    /// ```
+    pub fn read_counted<T: FromStr + 'static>(&mut self) -> Option<Vec<T>> {
+        self.source.read_counted(Stream::read)
+    }
+    /// Read n and then n values into a HashSet
+    #[cfg(feature = "std")]
+    pub fn read_counted_set<T: FromStr + 'static + Hash + Eq>(&mut self) -> Option<HashSet<T>> {
+        self.source.read_counted(Stream::read)
+    }
+    /// Read n and then n values into a BTreeSet
+    pub fn read_counted_btree_set<T: FromStr + 'static + Ord>(&mut self) -> Option<BTreeSet<T>> {
+        self.source.read_counted(Stream::read)
+    }
+    /// Read n and then n key value pairs into a HashMap
+    #[cfg(feature = "std")]
+    pub fn read_counted_map<K: FromStr + 'static + Hash + Eq, V: FromStr + 'static>(&mut self) -> Option<HashMap<K, V>> {
+        self.source.read_counted(Stream::read_pair)
+    }
+    /// Read rows x cols values, fails if any of them can't be read
+    ///
+    /// ``` rust
+    ///     use rcin::RInStream;
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new(&b"2 2\n1 2\n3 4\n#.\n.#\n"[..]));
+    ///     let (n, m) = (reader.read().unwrap(), reader.read().unwrap());
+    ///     assert_eq!(reader.read_matrix::<i32>(n, m), Some(vec![vec![1, 2], vec![3, 4]]));
+    ///     assert_eq!(reader.read_char_grid(2), Some(vec![vec!['#', '.'], vec!['.', '#']]));
+    /// ```
+    pub fn read_matrix<T: FromStr + 'static>(&mut self, rows: usize, cols: usize) -> Option<Vec<Vec<T>>> {
+        self.source.read_matrix(rows, cols)
+    }
+    /// Read the next rows lines as grid of characters
+    pub fn read_char_grid(&mut self, rows: usize) -> Option<Vec<Vec<char>>> {
+        self.source.read_char_grid(rows)
+    }
+    /// Read value parsed by a manipulator, see [`manip`]
+    pub fn read_with<T>(&mut self, manip: impl manip::Manipulator<T>) -> Option<T> {
+        self.source.read_with(manip)
+    }
+    /// Read token and parse it with f instead of `FromStr`, for foreign types
+    ///
+    /// ``` rust
+    ///     use rcin::RInStream;
+    ///     use std::time::Duration;
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new(&b"250ms"[..]));
+    ///     let timeout = reader.read_parsed(|s| s.strip_suffix("ms")?.parse().ok().map(Duration::from_millis));
+    ///     assert_eq!(timeout, Some(Duration::from_millis(250)));
+    /// ```
+    pub fn read_parsed<T>(&mut self, f: impl FnOnce(&str) -> Option<T>) -> Option<T> {
+        self.source.read_parsed(f)
+    }
+    /// Read enum value ignoring the case of the token
+    ///
+    /// The token is tried as is and in lower, upper and title case, so it works with exact `FromStr`
+    /// implementations. See [`token_enum`] for matching any case and aliases
+    ///
+    /// ``` rust
+    ///     use rcin::RInStream;
+    ///     use std::str::FromStr;
+    ///
+    ///     #[derive(Debug, PartialEq)]
+    ///     enum Mode { Read, Write }
+    ///     impl FromStr for Mode {
+    ///         type Err = ();
+    ///         fn from_str(s: &str) -> Result<Self, ()> {
+    ///             match s {
+    ///                 "Read" => Ok(Mode::Read),
+    ///                 "Write" => Ok(Mode::Write),
+    ///                 _ => Err(()),
+    ///             }
+    ///         }
+    ///     }
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new(&b"read WRITE"[..]));
+    ///     assert_eq!(reader.read_enum::<Mode>(), Some(Mode::Read));
+    ///     assert_eq!(reader.read_enum::<Mode>(), Some(Mode::Write));
+    /// ```
+    pub fn read_enum<T: FromStr + 'static>(&mut self) -> Option<T> {
+        self.source.read_enum()
+    }
+    /// Read token into the arena, tokens live as long as it without allocating one by one
+    ///
+    /// ``` rust
+    ///     use bumpalo::Bump;
+    ///     use rcin::RInStream;
+    ///
+    ///     let bump = Bump::new();
+    ///     let mut reader = RInStream::from_source(Box::new(&b"let x = y"[..]));
+    ///     let tokens: Vec<&str> = std::iter::from_fn(|| reader.read_in(&bump)).collect();
+    ///     drop(reader);
+    ///     assert_eq!(tokens, vec!["let", "x", "=", "y"]);
+    /// ```
+    #[cfg(feature = "bumpalo")]
+    pub fn read_in<'a>(&mut self, bump: &'a bumpalo::Bump) -> Option<&'a str> {
+        self.source.read_in(bump)
+    }
+    /// Read integer, clamped to the range of T if it doesn't fit
+    ///
+    /// `read_checked` fails with `ReadError::Overflow` on such integers instead
+    ///
+    /// ``` rust
+    ///     use rcin::{ReadError, RInStream};
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new(&b"300 -300 300 99999999999999999999999999999999999999999"[..]));
+    ///     assert_eq!(reader.read_checked::<u8>(), Err(ReadError::Overflow("300".into())));
+    ///     assert_eq!(reader.read_saturating::<i8>(), Some(-128));
+    ///     assert_eq!(reader.read_wrapping::<u8>(), Some(44));
+    ///     assert_eq!(reader.read_saturating::<i64>(), Some(i64::MAX));
+    /// ```
+    pub fn read_saturating<T: FastInt + 'static>(&mut self) -> Option<T> {
+        self.source.read_overflowing(true)
+    }
+    /// Read integer, wrapped around like with `wrapping_` arithmetic if it doesn't fit
+    pub fn read_wrapping<T: FastInt + 'static>(&mut self) -> Option<T> {
+        self.source.read_overflowing(false)
+    }
+    /// Read integer straight from the byte buffer, for inputs with millions of numbers
+    ///
+    /// Assumes well-formed input: ascii whitespace, an optional sign and ascii digits.
+    /// Anything else after the digits ends the number and overflow wraps around.
+    /// Returns None without consuming anything at a token that doesn't start with a digit
+    /// or a sign followed by a digit. Falls back to `read` while marks, `take_*` views,
+    /// pushed back input, `skipws(false)`, custom whitespace or line continuation are active
+    ///
+    /// ``` rust
+    ///     use rcin::RInStream;
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new(&b"3\n-10 20 +30"[..]));
+    ///     let n = reader.read_int_unchecked::<usize>().unwrap();
+    ///     let sum: i64 = (0..n).map(|_| reader.read_int_unchecked::<i64>().unwrap()).sum();
+    ///     assert_eq!(sum, 40);
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new("é 5".as_bytes()));
+    ///     assert_eq!(reader.read_int_unchecked::<i32>(), None);
+    ///     assert_eq!(reader.read_line().as_deref(), Some("é 5"));
+    ///     let mut reader = RInStream::from_source(Box::new(&b"- 5"[..]));
+    ///     assert_eq!(reader.read_int_unchecked::<i32>(), None);
+    ///     assert_eq!(reader.last_failure().unwrap().token.as_deref(), Some("-"));
+    ///     assert_eq!(reader.read_line().as_deref(), Some("- 5"));
+    /// ```
+    pub fn read_int_unchecked<T: FastInt + FromStr + 'static>(&mut self) -> Option<T> {
+        self.source.read_int_unchecked()
+    }
+    /// Read up to n integers straight from the byte buffer, without chars or strings in between
+    ///
+    /// Unlike `read_int_unchecked` overflow and tokens that aren't a number are detected, reading
+    /// stops at the first of them or at the end of input. Such a token is consumed
+    ///
+    /// ``` rust
+    ///     use rcin::RInStream;
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new(&b"1 -2\n3 99999999999999999999 4"[..]));
+    ///     assert_eq!(reader.read_ints_bulk(3), vec![1, -2, 3]);
+    ///     assert!(reader.read_ints_bulk(2).is_empty());
+    ///     assert_eq!(reader.read_ints_bulk(2), vec![4]);
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new(&b"12abc 5"[..]));
+    ///     assert!(reader.read_ints_bulk(3).is_empty());
+    ///     assert_eq!(reader.read_line().as_deref(), Some("5"));
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new(&b"1 abc 2"[..]));
+    ///     assert_eq!(reader.read_ints_bulk(3), vec![1]);
+    ///     assert_eq!(reader.read_ints_bulk(3), vec![2]);
+    /// ```
+    pub fn read_ints_bulk(&mut self, n: usize) -> Vec<i64> {
+        self.source.read_ints_bulk(n)
+    }
+    /// Read token as a symbol, repeated tokens don't allocate
+    ///
+    /// Useful for millions of short repeated strings, like categorical csv columns
+    ///
+    /// ``` rust
+    ///     use rcin::RInStream;
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new(&b"red green red"[..]));
+    ///     let colors: Vec<_> = std::iter::from_fn(|| reader.read_interned()).collect();
+    ///     assert_eq!(colors[0], colors[2]);
+    ///     assert_ne!(colors[0], colors[1]);
+    ///     assert_eq!(reader.resolve(colors[1]), Some("green"));
+    ///
+    ///     let other = RInStream::from_source(Box::new(&b""[..]));
+    ///     assert_eq!(other.resolve(colors[1]), None);
+    /// ```
+    pub fn read_interned(&mut self) -> Option<Symbol> {
+        self.source.read_interned()
+    }
+    /// Token of a symbol returned by `read_interned` of this stream
+    ///
+    /// None for symbols this stream doesn't know, a symbol of another stream can also resolve to an unrelated token
+    pub fn resolve(&self, sym: Symbol) -> Option<&str> {
+        self.source.symbols.get(sym.0).map(String::as_str)
+    }
+    /// Read value and report why it failed
+    pub fn read_checked<T: FromStr + 'static>(&mut self) -> Result<T, ReadError> {
+        self.source.read_checked()
+    }
+    /// Read value that has to be within range
+    ///
+    /// Values out of range are handled by the parse policy like tokens that don't parse
+    ///
+    /// ``` rust
+    ///     use rcin::{ParsePolicy, ReadError, RInStream};
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new(&b"5 11 x"[..]));
+    ///     assert_eq!(reader.read_in_range(1..=10), Ok(5));
+    ///     assert_eq!(reader.read_in_range(1..=10), Err(ReadError::OutOfRange("11".to_string())));
+    ///     assert_eq!(reader.last_failure().unwrap().token.as_deref(), Some("11"));
+    ///     assert_eq!(reader.read_in_range(1..=10), Err(ReadError::Parse("x".to_string())));
+    ///     assert_eq!(reader.read_in_range(1..=10), Err(ReadError::Eof));
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new(&b"11 x 7"[..]));
+    ///     reader.set_parse_policy(ParsePolicy::Skip);
+    ///     assert_eq!(reader.read_in_range(1..=10), Ok(7));
+    /// ```
+    pub fn read_in_range<T: FromStr + 'static + PartialOrd, R: RangeBounds<T>>(&mut self, range: R) -> Result<T, ReadError> {
+        self.source.read_in_range(range)
+    }
+    /// Read value of type A or, if it doesn't parse, of type B
+    ///
+    /// If the token is neither, it's left in the stream
+    ///
+    /// ``` rust
+    ///     use rcin::{Either, RInStream};
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new(&b"12 true x"[..]));
+    ///     assert_eq!(reader.read_either::<i32, bool>(), Some(Either::Left(12)));
+    ///     assert_eq!(reader.read_either::<i32, bool>(), Some(Either::Right(true)));
+    ///     assert_eq!(reader.read_either::<i32, bool>(), None);
+    ///     assert_eq!(reader.read::<char>(), Some('x'));
+    /// ```
+    pub fn read_either<A: FromStr + 'static, B: FromStr + 'static>(&mut self) -> Option<Either<A, B>> {
+        self.source.read_either()
+    }
+    /// Read token and convert its escape sequences (`\n`, `\t`, `\uXXXX`, ...) into real characters
+    ///
+    /// `\s` stands for a space, as tokens can't contain whitespace. `\u` takes exactly 4 hex digits,
+    /// chars outside the basic plane are written as a utf16 surrogate pair like in json.
+    /// Returns None if the token contains an unknown or malformed escape sequence
+    ///
+    /// ``` rust
+    ///     use rcin::RInStream;
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new(&br"tab\there \u00e9 \uD83D\uDE00 \u+041 \uD83D"[..]));
+    ///     assert_eq!(reader.read_unescaped(), Some("tab\there".to_string()));
+    ///     assert_eq!(reader.read_unescaped(), Some("é".to_string()));
+    ///     assert_eq!(reader.read_unescaped(), Some("😀".to_string()));
+    ///     assert_eq!(reader.read_unescaped(), None);
+    ///     assert_eq!(reader.read_unescaped(), None);
+    /// ```
+    pub fn read_unescaped(&mut self) -> Option<String> {
+        self.source.read_unescaped()
+    }
+    /// Read values until EOF or the first one that can't be parsed
+    ///
+    /// ``` rust
+    ///     use rcin::RInStream;
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new(&b"1 2 3\nrest\nof input"[..]));
+    ///     assert_eq!(reader.read_all::<i32>(), vec![1, 2, 3]);
+    ///     assert_eq!(reader.read_to_string(), "of input");
+    /// ```
+    pub fn read_all<T: FromStr + 'static>(&mut self) -> Vec<T> {
+        core::iter::from_fn(|| self.source.read()).collect()
+    }
+    /// Read all remaining lines
+    pub fn read_all_lines(&mut self) -> Vec<String> {
+        core::iter::from_fn(|| self.source.read_line()).collect()
+    }
+    /// Read lines while pred holds, the first line that fails it is not consumed
+    ///
+    /// ``` rust
+    ///     use rcin::RInStream;
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new(&b"> quoted\n> text\nreply"[..]));
+    ///     let quote = reader.read_lines_while(|line| line.starts_with('>'));
+    ///     assert_eq!(quote, vec!["> quoted", "> text"]);
+    ///     assert_eq!(reader.read_line(), Some("reply".to_string()));
+    /// ```
+    pub fn read_lines_while(&mut self, pred: impl FnMut(&str) -> bool) -> Vec<String> {
+        self.source.read_lines_while(pred)
+    }
+    /// Read a header line with the lines following it up to the next header
+    pub fn read_record(&mut self, is_header: impl FnMut(&str) -> bool) -> Option<Vec<String>> {
+        self.source.read_record(is_header)
+    }
+    /// Iterator over records that start with a line matching is_header
+    ///
+    /// Lines before the first header are a record of their own
+    ///
+    /// ``` rust
+    ///     use rcin::RInStream;
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new(&b"RECORD 1\na=1\nRECORD 2\nRECORD 3\nb=2\nc=3\n"[..]));
+    ///     let records: Vec<Vec<String>> = reader.split_records(|line| line.starts_with("RECORD")).collect();
+    ///     assert_eq!(records, [vec!["RECORD 1", "a=1"], vec!["RECORD 2"], vec!["RECORD 3", "b=2", "c=3"]]);
+    /// ```
+    pub fn split_records<'a>(
+        &'a mut self,
+        mut is_header: impl FnMut(&str) -> bool + 'a,
+    ) -> impl Iterator<Item = Vec<String>> + 'a {
+        core::iter::from_fn(move || self.source.read_record(&mut is_header))
+    }
+    /// Read consecutive lines indented by at least indent spaces and strip the indentation
+    ///
+    /// Blank lines inside the block are skipped, it ends at the first non-blank line with less
+    /// indentation. Blank lines right before that line are left unread
+    ///
+    /// ``` rust
+    ///     use rcin::RInStream;
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new(&b"def f():\n    x = 1\n\n      return x\n\nf()"[..]));
+    ///     reader.read_line();
+    ///     assert_eq!(reader.read_indented_block(4), vec!["x = 1", "  return x"]);
+    ///     assert_eq!(reader.read_line(), Some("".to_string()));
+    ///     assert_eq!(reader.read_line(), Some("f()".to_string()));
+    /// ```
+    pub fn read_indented_block(&mut self, indent: usize) -> Vec<String> {
+        self.source.read_indented_block(indent)
+    }
+    /// Iterator over the remaining whitespace separated tokens
+    ///
+    /// ``` rust
+    ///     use rcin::RInStream;
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new(&b"a bb ccc\n1,2,3"[..]));
+    ///     let lens: Vec<usize> = reader.words().take(3).map(|w| w.len()).collect();
+    ///     assert_eq!(lens, vec![1, 2, 3]);
+    ///     let sum: i32 = reader.split(',').map(|t| t.parse::<i32>().unwrap()).sum();
+    ///     assert_eq!(sum, 6);
+    /// ```
+    pub fn words(&mut self) -> impl Iterator<Item = String> + '_ {
+        core::iter::from_fn(move || self.source.read())
+    }
+    /// Iterator over token boundaries, line breaks and decode errors of the remaining input
+    ///
+    /// An error of the source is returned as `ReadError::Decode(DecodeError::Io)` and ends the events
+    ///
+    /// ``` rust
+    ///     use rcin::{DecodeError, Event, ReadError, RInStream};
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new(&b"ab c\n"[..]));
+    ///     let offsets: Vec<usize> = reader.events().map(|e| match e.unwrap() {
+    ///         Event::TokenStart(at) | Event::TokenEnd(at) | Event::Newline(at) | Event::DecodeError(at) => at.offset,
+    ///     }).collect();
+    ///     assert_eq!(offsets, vec![0, 2, 3, 4, 4]);
+    ///
+    ///     struct Broken;
+    ///     impl std::io::Read for Broken {
+    ///         fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+    ///             Err(std::io::ErrorKind::BrokenPipe.into())
+    ///         }
+    ///     }
+    ///     let mut reader = RInStream::from_source(Box::new(Broken));
+    ///     let events: Vec<_> = reader.events().collect();
+    ///     assert_eq!(events, vec![Err(ReadError::Decode(DecodeError::Io))]);
+    /// ```
+    pub fn events(&mut self) -> Events<'_> {
+        Events {
+            stream: self,
+            in_token: false,
+            pending: None,
+            failed: false,
+        }
+    }
+    /// Iterator over the remaining input split by the delimiter
+    pub fn split(&mut self, delim: char) -> impl Iterator<Item = String> + '_ {
+        core::iter::from_fn(move || self.source.read_until(delim))
+    }
+    /// Read record up to the next `\0`, like the output of `find -print0`, line breaks are part of it
+    pub fn read_until_nul(&mut self) -> Option<String> {
+        self.source.read_until('\0')
+    }
+    /// Iterator over the remaining `\0` separated records
+    ///
+    /// ``` rust
+    ///     use rcin::RInStream;
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new(&b"./a b\0./new\nline\0"[..]));
+    ///     let files: Vec<String> = reader.nul_records().collect();
+    ///     assert_eq!(files, ["./a b", "./new\nline"]);
+    /// ```
+    pub fn nul_records(&mut self) -> impl Iterator<Item = String> + '_ {
+        self.split('\0')
+    }
+    /// Read everything until EOF
+    pub fn read_to_string(&mut self) -> String {
+        self.source.read_to_string()
+    }
+    /// Read the next token without consuming it
+    ///
+    /// ``` rust
+    ///     use rcin::RInStream;
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new(&b"add 1 2\nquit"[..]));
+    ///     assert_eq!(reader.peek_token(), Some("add"));
+    ///     assert_eq!(reader.peek_line(), Some("add 1 2"));
+    ///     assert_eq!(reader.read::<String>(), Some("add".to_string()));
+    /// ```
+    pub fn peek_token(&mut self) -> Option<&str> {
+        self.source.peek_token()
+    }
+    /// Read the next line without consuming it
+    pub fn peek_line(&mut self) -> Option<&str> {
+        self.source.peek_line()
+    }
+    /// Next n chars without consuming them, at most 16 and fewer at the end of input
+    ///
+    /// ``` rust
+    ///     use rcin::RInStream;
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new(&b"<= 2"[..]));
+    ///     let op = match reader.lookahead(2) {
+    ///         ['<', '='] => "le",
+    ///         ['<', _] => "lt",
+    ///         _ => "other",
+    ///     };
+    ///     assert_eq!(op, "le");
+    ///     assert_eq!(reader.lookahead(8), ['<', '=', ' ', '2']);
+    ///     assert_eq!(reader.read::<String>(), Some("<=".to_string()));
+    /// ```
+    pub fn lookahead(&mut self, n: usize) -> &[char] {
+        self.source.lookahead(n)
+    }
     /// Read line
     pub fn read_line(&mut self) -> Option<String> {
         self.source.read_line()
     }
+    /// Read line, borrowed from the buffer when it's there completely instead of copied
+    ///
+    /// Saves an allocation per line when most lines are only inspected, like in filters
+    ///
+    /// ``` rust
+    ///     use rcin::RInStream;
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new(&b"INFO ok\nERROR disk full\nINFO ok\n"[..]));
+    ///     let mut errors = Vec::new();
+    ///     while let Some(line) = reader.read_line_borrowed() {
+    ///         if line.starts_with("ERROR") {
+    ///             errors.push(line.into_owned());
+    ///         }
+    ///     }
+    ///     assert_eq!(errors, vec!["ERROR disk full"]);
+    /// ```
+    ///
+    /// At the end the source is read only once, a terminal isn't waited on twice
+    ///
+    /// ``` rust
+    ///     use rcin::RInStream;
+    ///     use std::sync::atomic::{AtomicUsize, Ordering};
+    ///
+    ///     static READS: AtomicUsize = AtomicUsize::new(0);
+    ///     struct Empty;
+    ///     impl std::io::Read for Empty {
+    ///         fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+    ///             READS.fetch_add(1, Ordering::Relaxed);
+    ///             Ok(0)
+    ///         }
+    ///     }
+    ///     let mut reader = RInStream::from_source(Box::new(Empty));
+    ///     assert_eq!(reader.read_line_borrowed(), None);
+    ///     assert_eq!(READS.load(Ordering::Relaxed), 1);
+    /// ```
+    ///
+    /// A line cut by the end of the buffer is completed by refills and still borrowed if it fits
+    ///
+    /// ``` rust
+    ///     use rcin::RInStream;
+    ///     use std::borrow::Cow;
+    ///
+    ///     // returns at most 3 bytes per read, like a slow pipe
+    ///     struct Trickle(&'static [u8]);
+    ///     impl std::io::Read for Trickle {
+    ///         fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+    ///             let n = buf.len().min(3).min(self.0.len());
+    ///             buf[..n].copy_from_slice(&self.0[..n]);
+    ///             self.0 = &self.0[n..];
+    ///             Ok(n)
+    ///         }
+    ///     }
+    ///     let mut reader = RInStream::new(Box::new(Trickle(b"ab\nlonger line\n")), 16);
+    ///     assert_eq!(reader.read_line_borrowed().as_deref(), Some("ab"));
+    ///     let line = reader.read_line_borrowed().unwrap();
+    ///     assert!(matches!(line, Cow::Borrowed("longer line")));
+    ///     drop(line);
+    ///     assert_eq!(reader.read_line_borrowed(), None);
+    /// ```
+    pub fn read_line_borrowed(&mut self) -> Option<Cow<'_, str>> {
+        self.source.read_line_borrowed()
+    }
+    /// Read line and report why it failed
+    pub fn read_line_checked(&mut self) -> Result<String, ReadError> {
+        self.source.read_line_checked()
+    }
+    /// Record lines read by `read_line` in a history, None stops recording
+    #[cfg(feature = "std")]
+    pub fn set_history(&mut self, history: Option<interactive::History>) {
+        self.source.history = history;
+    }
+    /// Lines recorded since `set_history`
+    #[cfg(feature = "std")]
+    pub fn history(&self) -> Option<&interactive::History> {
+        self.source.history.as_ref()
+    }
+    /// Mutable access to the recorded lines, to clear or save them
+    #[cfg(feature = "std")]
+    pub fn history_mut(&mut self) -> Option<&mut interactive::History> {
+        self.source.history.as_mut()
+    }
+    /// Write `label [default]: ` to out and read a line, an empty one takes the default
+    ///
+    /// ``` rust
+    ///     use rcin::{ReadError, RInStream};
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new(&b"\n9000\nhttp\n"[..]));
+    ///     let mut out = Vec::new();
+    ///     assert_eq!(reader.prompt_with_default(&mut out, "Port", 8080u16), Ok(8080));
+    ///     assert_eq!(reader.prompt_with_default(&mut out, "Port", 8080u16), Ok(9000));
+    ///     assert_eq!(reader.prompt_with_default(&mut out, "Port", 8080u16), Err(ReadError::Parse("http".into())));
+    ///     assert!(String::from_utf8(out).unwrap().starts_with("Port [8080]: "));
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn prompt_with_default<T>(&mut self, out: &mut dyn io::Write, label: &str, default: T) -> Result<T, ReadError>
+    where
+        T: FromStr + 'static + fmt::Display,
+    {
+        self.source.prompt_with_default(out, label, default)
+    }
+    /// Grow the buffer up to max bytes while reads fill it and shrink it back when they don't, None keeps its size
+    ///
+    /// The buffer never gets smaller than it was when this was set
+    ///
+    /// ``` rust
+    ///     use rcin::RInStream;
+    ///
+    ///     let text = std::io::Cursor::new("word ".repeat(1000));
+    ///     let mut reader = RInStream::new(Box::new(text), 64);
+    ///     reader.set_adaptive_buffer(Some(1024));
+    ///     assert_eq!(reader.read_all::<String>().len(), 1000);
+    ///     assert_eq!(reader.buffer_size(), 1024);
+    /// ```
+    pub fn set_adaptive_buffer(&mut self, max: Option<usize>) {
+        self.source.set_adaptive_buffer(max)
+    }
+    /// Current size of the buffer in bytes
+    pub fn buffer_size(&self) -> usize {
+        self.source.buf.len()
+    }
+    /// Limit the length of tokens in chars, longer ones are skipped and fail with `ReadError::TokenTooLong`
+    ///
+    /// Without a limit a source without whitespace makes a token grow unbounded
+    ///
+    /// ``` rust
+    ///     use rcin::{ReadError, RInStream};
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new(&b"123456789 42\nlong line\nok"[..]));
+    ///     reader.set_max_token_len(Some(4));
+    ///     reader.set_max_line_len(Some(4));
+    ///     assert_eq!(reader.read_checked::<i32>(), Err(ReadError::TokenTooLong));
+    ///     assert_eq!(reader.read_checked::<i32>(), Ok(42));
+    ///     assert_eq!(reader.read_line_checked(), Err(ReadError::LineTooLong));
+    ///     assert_eq!(reader.read_line_checked(), Ok("ok".to_string()));
+    /// ```
+    pub fn set_max_token_len(&mut self, max: Option<usize>) {
+        self.source.max_token_len = max;
+    }
+    /// Limit the length of lines in chars, longer ones are skipped and fail with `ReadError::LineTooLong`
+    pub fn set_max_line_len(&mut self, max: Option<usize>) {
+        self.source.max_line_len = max;
+    }
+    /// Read a single CSV record, quoted fields can contain delimiters and newlines
+    ///
+    /// ``` rust
+    ///     use rcin::RInStream;
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new(&b"a,\"b,\"\"c\"\"\"\n"[..]));
+    ///     assert_eq!(reader.read_csv_record(','), Some(vec!["a".to_string(), "b,\"c\"".to_string()]));
+    ///     assert_eq!(reader.read_csv_record(','), None);
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new(&b"a\rb,c\r\nd\r\n"[..]));
+    ///     assert_eq!(reader.read_csv_record(','), Some(vec!["a\rb".to_string(), "c".to_string()]));
+    ///     assert_eq!(reader.read_csv_record(','), Some(vec!["d".to_string()]));
+    /// ```
+    pub fn read_csv_record(&mut self, delimiter: char) -> Option<Vec<String>> {
+        self.source.read_csv_record(delimiter)
+    }
+    /// Read a single CSV record and report why it failed
+    ///
+    /// Invalid utf8 fails the record with `ReadError::Decode` instead of ending it, the rest of
+    /// the record stays unread
+    ///
+    /// ``` rust
+    ///     use rcin::{DecodeError, ReadError, RInStream};
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new(&b"a,b\xff,c\nd\n"[..]));
+    ///     assert!(matches!(reader.read_csv_record_checked(','), Err(ReadError::Decode(DecodeError::InvalidUtf8(_)))));
+    ///     assert_eq!(reader.read_line().as_deref(), Some(",c"));
+    ///     assert_eq!(reader.read_csv_record_checked(','), Ok(vec!["d".to_string()]));
+    ///     assert_eq!(reader.read_csv_record_checked(','), Err(ReadError::Eof));
+    /// ```
+    pub fn read_csv_record_checked(&mut self, delimiter: char) -> Result<Vec<String>, ReadError> {
+        self.source.read_csv_record_checked(delimiter)
+    }
+    /// Read a CSV header, later records can be read by column name
+    pub fn read_header(&mut self, delimiter: char) -> Option<Vec<String>> {
+        self.source.read_header(delimiter)
+    }
+    /// Read a CSV record as a map from header column to value
+    ///
+    /// Returns None if no header was read. Columns without a value are left out
+    ///
+    /// ``` rust
+    ///     use rcin::RInStream;
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new(&b"name;age\nbob;42\n"[..]));
+    ///     reader.read_header(';');
+    ///     let record = reader.read_record_map().unwrap();
+    ///     assert_eq!(record["age"], "42");
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn read_record_map(&mut self) -> Option<HashMap<String, String>> {
+        self.source.read_record_map()
+    }
+    /// Iterator over the remaining CSV records
+    pub fn csv_records(&mut self, delimiter: char) -> CsvRecords<'_> {
+        CsvRecords {
+            stream: self,
+            delimiter,
+        }
+    }
+    /// Read a line and deserialize it from json, blank lines are skipped
+    ///
+    /// A line that isn't valid json is reported to the error handler and by `last_failure`
+    ///
+    /// ``` rust
+    ///     use rcin::{ReadError, RInStream};
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new(&b"[1, 2]\n\n{oops\n"[..]));
+    ///     assert_eq!(reader.read_json::<Vec<i32>>(), Some(vec![1, 2]));
+    ///     assert_eq!(reader.read_json::<Vec<i32>>(), None);
+    ///     let failure = reader.last_failure().unwrap();
+    ///     assert_eq!(failure.error, ReadError::Parse("{oops".to_string()));
+    ///     assert_eq!(failure.position.line, 3);
+    /// ```
+    #[cfg(feature = "serde_json")]
+    pub fn read_json<T: serde::de::DeserializeOwned>(&mut self) -> Option<T> {
+        self.source.read_json()
+    }
+    /// Iterator over the remaining json lines, stops at the first invalid one
+    #[cfg(feature = "serde_json")]
+    pub fn json_lines<T: serde::de::DeserializeOwned>(&mut self) -> JsonLines<'_, T> {
+        JsonLines {
+            stream: self,
+            marker: core::marker::PhantomData,
+        }
+    }
+    /// Read the next match of regex, skipping everything before it
+    ///
+    /// The input is scanned line by line, so matches can't span multiple lines
+    ///
+    /// ``` rust
+    ///     use rcin::RInStream;
+    ///     use regex::Regex;
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new(&b"log\nuser=bob id=42 ok"[..]));
+    ///     let id = Regex::new(r"id=\d+").unwrap();
+    ///     assert_eq!(reader.read_match(&id), Some("id=42".to_string()));
+    ///     assert_eq!(reader.read::<String>(), Some("ok".to_string()));
+    /// ```
+    #[cfg(feature = "regex")]
+    pub fn read_match(&mut self, regex: &regex::Regex) -> Option<String> {
+        self.source.read_match(regex)
+    }
+    /// Skip everything before the next match of regex, returns false if there is none
+    #[cfg(feature = "regex")]
+    pub fn skip_until_match(&mut self, regex: &regex::Regex) -> bool {
+        self.source.skip_until_match(regex)
+    }
+    /// Run a nom streaming parser, more lines are read while it returns `Incomplete`
+    ///
+    /// Input the parser didn't consume stays in the stream, nothing is consumed if it fails.
+    /// `Incomplete` at the end of input fails with `ReadError::Eof`, a line longer than
+    /// `set_max_line_len` is skipped like by `read_line` and fails with `ReadError::LineTooLong`
+    ///
+    /// ``` rust
+    ///     use nom::bytes::streaming::{tag, take_until};
+    ///     use nom::combinator::map;
+    ///     use nom::sequence::delimited;
+    ///     use nom::Parser;
+    ///     use rcin::RInStream;
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new(&b"(multi\nline) 42"[..]));
+    ///     let group = reader.parse_nom(|i| map(delimited(tag("("), take_until(")"), tag(")")), str::to_string).parse(i));
+    ///     assert_eq!(group.unwrap(), "multi\nline");
+    ///     assert_eq!(reader.read::<i32>(), Some(42));
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new(&b"a\nbcdefghij\nxyz\n"[..]));
+    ///     reader.set_max_line_len(Some(4));
+    ///     let block = reader.parse_nom(|i| take_until("END").parse(i).map(|(rest, _)| (rest, ())));
+    ///     assert_eq!(block, Err(rcin::ReadError::LineTooLong));
+    ///     assert_eq!(reader.read_line().as_deref(), Some("a"));
+    ///     assert_eq!(reader.read_line().as_deref(), Some("xyz"));
+    /// ```
+    #[cfg(feature = "nom")]
+    pub fn parse_nom<O>(&mut self, parser: impl FnMut(&str) -> nom::IResult<&str, O>) -> Result<O, ReadError> {
+        self.source.parse_nom(parser)
+    }
+    /// Lex the remaining input with a logos lexer instead of splitting at whitespace
+    ///
+    /// ``` rust
+    ///     use logos::Logos;
+    ///     use rcin::RInStream;
+    ///
+    ///     #[derive(Logos, Debug, PartialEq)]
+    ///     #[logos(skip r"[ \t\n]+")]
+    ///     enum Token {
+    ///         #[token("let")]
+    ///         Let,
+    ///         #[token("=")]
+    ///         Assign,
+    ///         #[regex("[a-z]+")]
+    ///         Ident,
+    ///         #[regex("[0-9]+")]
+    ///         Number,
+    ///     }
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new(&b"let x = 42\nrest"[..]));
+    ///     let mut tokens = reader.logos::<Token>();
+    ///     assert_eq!(tokens.next_token(), Some(Ok(Token::Let)));
+    ///     assert_eq!(tokens.next_token(), Some(Ok(Token::Ident)));
+    ///     assert_eq!(tokens.slice(), "x");
+    ///     assert_eq!(tokens.by_ref().take(2).count(), 2);
+    ///     drop(tokens);
+    ///     assert_eq!(reader.read::<String>(), Some("rest".to_string()));
+    /// ```
+    #[cfg(feature = "logos")]
+    pub fn logos<T>(&mut self) -> LogosTokens<'_, T>
+    where
+        T: for<'s> logos::Logos<'s, Source = str, Extras = ()>,
+    {
+        let at = self.source.pos;
+        LogosTokens {
+            stream: self,
+            buf: String::new(),
+            used: 0,
+            at,
+            last: 0,
+            eof: false,
+            marker: core::marker::PhantomData,
+        }
+    }
+    /// Read the next grapheme cluster (user-perceived character)
+    ///
+    /// ``` rust
+    ///     use rcin::RInStream;
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new("e\u{301}👍🏽!".as_bytes()));
+    ///     assert_eq!(reader.read_grapheme(), Some("e\u{301}".to_string()));
+    ///     assert_eq!(reader.graphemes().collect::<Vec<_>>(), vec!["👍🏽", "!"]);
+    /// ```
+    #[cfg(feature = "unicode-segmentation")]
+    pub fn read_grapheme(&mut self) -> Option<String> {
+        self.source.read_grapheme()
+    }
+    /// Iterator over the remaining grapheme clusters
+    #[cfg(feature = "unicode-segmentation")]
+    pub fn graphemes(&mut self) -> impl Iterator<Item = String> + '_ {
+        core::iter::from_fn(move || self.source.read_grapheme())
+    }
+    /// Skip all chars until needle, returns false if it wasn't found
+    ///
+    /// If include is true, the needle is skipped as well
+    pub fn skip_until(&mut self, needle: char, include: bool) -> bool {
+        self.source.skip_until(needle, include)
+    }
+    /// Skip all chars until needle string, returns false if it wasn't found
+    ///
+    /// ``` rust
+    ///     use rcin::RInStream;
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new(&b"junk BEGIN 1 2 # 3"[..]));
+    ///     assert!(reader.skip_until_str("BEGIN", false));
+    ///     assert_eq!(reader.read::<String>(), Some("BEGIN".to_string()));
+    ///     assert!(reader.skip_until('#', true));
+    ///     assert_eq!(reader.read::<i32>(), Some(3));
+    ///     assert!(!reader.skip_until_str("END", true));
+    /// ```
+    pub fn skip_until_str(&mut self, needle: &str, include: bool) -> bool {
+        self.source.skip_until_str(needle, include)
+    }
     /// Skip all chars until next newline
     pub fn skip_line(&mut self) {
         self.source.skip_line()
     }
+    /// Call callback every time at least interval_bytes more input was consumed
+    ///
+    /// The callback is invoked when the buffer is refilled, so it's not more precise than the buffer size.
+    ///
+    /// ``` rust
+    ///     use std::fs::File;
+    ///     use rcin::RInStream;
+    ///
+    ///     let mut reader = RInStream::from_file(File::open("test.txt").unwrap());
+    ///     reader.on_progress(1024 * 1024, |p| {
+    ///         eprintln!("{} of {} bytes", p.consumed, p.total.unwrap());
+    ///     });
+    ///     reader.read_all_lines();
+    /// ```
+    pub fn on_progress(&mut self, interval_bytes: usize, callback: impl FnMut(Progress) + Send + 'static) {
+        self.source.progress = Some(ProgressHook {
+            interval: interval_bytes,
+            next: self.source.consumed + interval_bytes,
+            callback: Box::new(callback),
+        });
+    }
+    /// Position of the next char
+    ///
+    /// ``` rust
+    ///     use rcin::RInStream;
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new("ab\nçd".as_bytes()));
+    ///     reader.read_line();
+    ///     reader.read_char();
+    ///     let pos = reader.position();
+    ///     assert_eq!((pos.offset, pos.line, pos.column), (5, 2, 2));
+    /// ```
+    pub fn position(&self) -> Position {
+        self.source.pos
+    }
+    /// Counters collected since the stream was created
+    ///
+    /// ``` rust
+    ///     use rcin::RInStream;
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new(&b"1 2 x"[..]));
+    ///     let values: Vec<i32> = reader.read_all();
+    ///     let stats = reader.stats();
+    ///     assert_eq!(values.len(), 2);
+    ///     assert_eq!((stats.bytes_read, stats.tokens, stats.parse_failures), (5, 3, 1));
+    /// ```
+    pub fn stats(&self) -> Stats {
+        self.source.stats
+    }
+    /// Set the chars that separate tokens
+    ///
+    /// ``` rust
+    ///     use rcin::{RInStream, WhitespaceMode};
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new(&b"a,b c"[..]));
+    ///     reader.set_whitespace(WhitespaceMode::Custom([','].iter().cloned().collect()));
+    ///     assert_eq!(reader.read::<String>(), Some("a".to_string()));
+    ///     assert_eq!(reader.read::<String>(), Some("b c".to_string()));
+    /// ```
+    pub fn set_whitespace(&mut self, mode: WhitespaceMode) {
+        self.source.whitespace = mode;
+    }
+    /// Kind of line breaks read so far, to warn about or adapt to mixed line endings
+    ///
+    /// ``` rust
+    ///     use rcin::{LineEnding, RInStream};
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new(&b"a\r\nb\r\nc\n"[..]));
+    ///     reader.read_line();
+    ///     assert_eq!(reader.detect_line_ending(), LineEnding::CrLf);
+    ///     reader.read_to_string();
+    ///     assert_eq!(reader.detect_line_ending(), LineEnding::Mixed);
+    /// ```
+    pub fn detect_line_ending(&self) -> LineEnding {
+        self.source.line_ending()
+    }
+    /// Read `\r\n` as `\n` in all reads, so lines don't end with `\r`
+    ///
+    /// ``` rust
+    ///     use rcin::RInStream;
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new(&b"a\r\nb\r\n"[..]));
+    ///     reader.set_strip_cr(true);
+    ///     assert_eq!(reader.read_all_lines(), vec!["a", "b"]);
+    /// ```
+    pub fn set_strip_cr(&mut self, enabled: bool) {
+        self.source.strip_cr = enabled;
+    }
+    /// Join lines ending in a backslash with the next line in all reads, the backslash and line break are dropped
+    ///
+    /// ``` rust
+    ///     use rcin::RInStream;
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new(&b"CFLAGS = -O2 \\\n  -Wall\nnext"[..]));
+    ///     reader.set_line_continuation(true);
+    ///     assert_eq!(reader.read_line(), Some("CFLAGS = -O2   -Wall".to_string()));
+    ///     assert_eq!(reader.read_line(), Some("next".to_string()));
+    /// ```
+    pub fn set_line_continuation(&mut self, enabled: bool) {
+        self.source.continuation = enabled;
+    }
+    /// Parse T with f instead of `FromStr` in all reads of the stream, like `read` or `read_all`
+    ///
+    /// ``` rust
+    ///     use rcin::RInStream;
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new(&b"yes no 1"[..]));
+    ///     reader.register_parser(|s| match s {
+    ///         "yes" | "1" => Some(true),
+    ///         "no" | "0" => Some(false),
+    ///         _ => None,
+    ///     });
+    ///     assert_eq!(reader.read_all::<bool>(), vec![true, false, true]);
+    /// ```
+    pub fn register_parser<T: 'static>(&mut self, f: impl Fn(&str) -> Option<T> + Send + 'static) {
+        self.source.register_parser(f);
+    }
+    /// Choose what `read` and `read_checked` do with tokens that don't parse
+    ///
+    /// ``` rust
+    ///     use rcin::{ParsePolicy, RInStream};
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new(&b"1 n/a 3 ? 5"[..]));
+    ///     reader.set_parse_policy(ParsePolicy::Skip);
+    ///     assert_eq!(reader.read_all::<i32>(), vec![1, 3, 5]);
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new(&b"1 n/a 3"[..]));
+    ///     reader.set_parse_policy(ParsePolicy::UseDefault);
+    ///     assert_eq!(reader.read_all::<i32>(), vec![1, 0, 3]);
+    /// ```
+    pub fn set_parse_policy(&mut self, policy: ParsePolicy) {
+        self.source.policy = policy;
+    }
+    /// Let `ParsePolicy::UseDefault` substitute `T::default()`
+    pub fn register_default<T: Default + 'static>(&mut self) {
+        self.source.register_default::<T>();
+    }
+    /// Normalize tokens and lines to NFC, chars read one by one stay as they are
+    ///
+    /// Text typed on macOS is often decomposed (NFD), normalized it compares equal to literals
+    ///
+    /// ``` rust
+    ///     use rcin::RInStream;
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new("Zu\u{308}rich\nCafe\u{301} au lait".as_bytes()));
+    ///     reader.set_normalize_nfc(true);
+    ///     assert_eq!(reader.read::<String>(), Some("Zürich".to_string()));
+    ///     assert_eq!(reader.read_line(), Some("Café au lait".to_string()));
+    /// ```
+    #[cfg(feature = "unicode-normalization")]
+    pub fn set_normalize_nfc(&mut self, enabled: bool) {
+        self.source.nfc = enabled;
+    }
+    /// Print every token and line read to stderr with its position, also enabled by setting `RCIN_TRACE`
+    ///
+    /// Shows where parsing gets misaligned with the input
+    #[cfg(feature = "std")]
+    pub fn set_trace(&mut self, enabled: bool) {
+        self.source.trace = enabled;
+    }
+    /// Call f on every failed read and invalid utf8 in the input, like to log and count bad records
+    ///
+    /// ``` rust
+    ///     use rcin::RInStream;
+    ///     use std::sync::{Arc, Mutex};
+    ///
+    ///     let bad = Arc::new(Mutex::new(Vec::new()));
+    ///     let log = bad.clone();
+    ///     let mut reader = RInStream::from_source(Box::new(&b"1 x 2 y"[..]));
+    ///     reader.set_error_handler(move |failure| log.lock().unwrap().push(failure.token.clone()));
+    ///     let _ = (0..4).map(|_| reader.read::<i32>()).count();
+    ///     assert_eq!(*bad.lock().unwrap(), [Some("x".to_string()), Some("y".to_string())]);
+    /// ```
+    pub fn set_error_handler(&mut self, f: impl FnMut(&ReadFailure) + Send + 'static) {
+        self.source.error_handler = Some(Box::new(f));
+    }
+    /// Toggle skipping whitespace around tokens, like c++ skipws/noskipws
+    ///
+    /// Without skipping, every whitespace char is read as a token of its own
+    ///
+    /// ``` rust
+    ///     use rcin::RInStream;
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new(&b"a b"[..]));
+    ///     reader.skipws(false);
+    ///     let chars: Vec<char> = reader.read_all();
+    ///     assert_eq!(chars, vec!['a', ' ', 'b']);
+    /// ```
+    pub fn skipws(&mut self, skip: bool) {
+        self.source.skipws = skip;
+    }
+    /// Start recording consumed input, so it can be rolled back
+    ///
+    /// Marks can be nested, every mark has to be finished with either rollback or commit
+    pub fn mark(&mut self) {
+        self.source.mark()
+    }
+    /// Return all input consumed since the last mark back to the stream
+    pub fn rollback(&mut self) {
+        self.source.rollback()
+    }
+    /// Drop the last mark keeping the consumed input
+    pub fn commit(&mut self) {
+        self.source.commit()
+    }
+    /// Run f and roll back all input it consumed if it returns None
+    ///
+    /// Tokens read by f are returned to the limits of [`take_tokens`](RInStream::take_tokens) views as well
+    ///
+    /// ``` rust
+    ///     use rcin::RInStream;
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new(&b"1 apple"[..]));
+    ///     let pair = reader.attempt(|r| Some((r.read::<i32>()?, r.read::<i32>()?)));
+    ///     assert_eq!(pair, None);
+    ///     assert_eq!(reader.read::<i32>(), Some(1));
+    ///     assert_eq!(reader.read::<String>(), Some("apple".to_string()));
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new(&b"1 x 3"[..]));
+    ///     let mut view = reader.take_tokens(2);
+    ///     assert_eq!(view.attempt(|r| Some((r.read::<i32>()?, r.read::<i32>()?))), None);
+    ///     assert_eq!(view.read_all::<String>(), vec!["1", "x"]);
+    ///     drop(view);
+    ///     assert_eq!(reader.stats().tokens, 2);
+    ///     assert_eq!(reader.read::<i32>(), Some(3));
+    /// ```
+    pub fn attempt<T>(&mut self, f: impl FnOnce(&mut RInStream) -> Option<T>) -> Option<T> {
+        self.mark();
+        let res = f(self);
+        if res.is_some() {
+            self.commit();
+        } else {
+            self.rollback();
+        }
+        res
+    }
+    /// Read lines on a background thread and send them over a channel
+    ///
+    /// The thread stops when the stream ends or the receiver is dropped
+    ///
+    /// ``` rust
+    ///     use rcin::RInStream;
+    ///
+    ///     let reader = RInStream::from_source(Box::new(&b"a\nb\n"[..]));
+    ///     let lines: Vec<String> = reader.into_line_channel().iter().collect();
+    ///     assert_eq!(lines, vec!["a", "b"]);
+    /// ```
+    #[cfg(all(feature = "std", not(target_family = "wasm")))]
+    pub fn into_line_channel(mut self) -> Receiver<String> {
+        line_channel(move || self.read_line())
+    }
+    /// Restrict the stream to the next k tokens, after them it reports EOF until the view is dropped
+    ///
+    /// ``` rust
+    ///     use rcin::RInStream;
+    ///
+    ///     fn sum(reader: &mut RInStream) -> i32 {
+    ///         reader.read_all::<i32>().iter().sum()
+    ///     }
+    ///     let mut reader = RInStream::from_source(Box::new(&b"1 2 3\n4 5"[..]));
+    ///     assert_eq!(sum(&mut reader.take_tokens(2)), 3);
+    ///     assert_eq!(sum(&mut reader.take_lines(1)), 3);
+    ///     assert_eq!(sum(&mut reader), 9);
+    /// ```
+    pub fn take_tokens(&mut self, k: usize) -> Take<'_> {
+        self.source.budgets.push(Budget::Tokens(k));
+        Take { stream: self }
+    }
+    /// Restrict the stream to the next n lines, after them it reports EOF until the view is dropped
+    pub fn take_lines(&mut self, n: usize) -> Take<'_> {
+        self.source.budgets.push(Budget::Lines(n));
+        Take { stream: self }
+    }
     /// Stream becomes invalid as soon as the source returns an error
     pub fn valid(&self) -> bool {
         self.source.valid()
     }
+    /// Hexdump with ascii of the next n buffered bytes without consuming them, like `hexdump -C`
+    ///
+    /// ``` rust
+    ///     use rcin::RInStream;
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new(&b"id;name\r\n1;\xc3\xa9"[..]));
+    ///     assert_eq!(
+    ///         reader.debug_peek(16),
+    ///         "00000000  69 64 3b 6e 61 6d 65 0d  0a 31 3b c3 a9           |id;name..1;..|"
+    ///     );
+    /// ```
+    pub fn debug_peek(&mut self, n: usize) -> String {
+        self.source.debug_peek(n)
+    }
+    /// Token, type and position of the most recent read that failed
+    ///
+    /// ``` rust
+    ///     use rcin::{ReadError, RInStream};
+    ///
+    ///     let mut reader = RInStream::from_source(Box::new(&b"1 x"[..]));
+    ///     assert_eq!(reader.read::<i32>(), Some(1));
+    ///     assert_eq!(reader.read::<i32>(), None);
+    ///     let failure = reader.last_failure().unwrap();
+    ///     assert_eq!(failure.token.as_deref(), Some("x"));
+    ///     assert_eq!(failure.type_name, "i32");
+    ///     assert_eq!(failure.position.column, 3);
+    ///     assert_eq!(failure.error, ReadError::Parse("x".into()));
+    /// ```
+    pub fn last_failure(&self) -> Option<&ReadFailure> {
+        self.source.last_failure.as_ref()
+    }
+}
+
+
+/// Read primitives shared by [`RInStream`] and the locked global stream, for helpers working with either
+///
+/// ``` rust
+///     use rcin::{RcinRead, RInStream};
+///
+///     fn read_point(r: &mut impl RcinRead) -> Option<(i32, i32)> {
+///         Some((r.read()?, r.read()?))
+///     }
+///
+///     let mut stream = RInStream::from_source(Box::new(&b"1 2"[..]));
+///     assert_eq!(read_point(&mut stream), Some((1, 2)));
+/// ```
+pub trait RcinRead {
+    /// Read value
+    fn read<T: FromStr + 'static>(&mut self) -> Option<T>;
+    /// Read value and report why it failed
+    fn read_checked<T: FromStr + 'static>(&mut self) -> Result<T, ReadError>;
+    /// Read line
+    fn read_line(&mut self) -> Option<String>;
+    /// Read line and report why it failed
+    fn read_line_checked(&mut self) -> Result<String, ReadError>;
+    /// Read the next character (can be whitespace)
+    fn read_char(&mut self) -> Option<char>;
+    /// Read the next token without consuming it
+    fn peek_token(&mut self) -> Option<&str>;
+    /// Position of the next char
+    fn position(&self) -> Position;
+    /// Whether the source hasn't failed yet
+    fn valid(&self) -> bool;
+}
+// RcinRead for a wrapper of the stream, field is the Stream it reads
+macro_rules! impl_rcin_read {
+    ($ty:ty, $field:ident) => {
+        impl RcinRead for $ty {
+            fn read<T: FromStr + 'static>(&mut self) -> Option<T> {
+                self.$field.read()
+            }
+            fn read_checked<T: FromStr + 'static>(&mut self) -> Result<T, ReadError> {
+                self.$field.read_checked()
+            }
+            fn read_line(&mut self) -> Option<String> {
+                self.$field.read_line()
+            }
+            fn read_line_checked(&mut self) -> Result<String, ReadError> {
+                self.$field.read_line_checked()
+            }
+            fn read_char(&mut self) -> Option<char> {
+                self.$field.pop_char()
+            }
+            fn peek_token(&mut self) -> Option<&str> {
+                self.$field.peek_token()
+            }
+            fn position(&self) -> Position {
+                self.$field.pos
+            }
+            fn valid(&self) -> bool {
+                self.$field.valid()
+            }
+        }
+    };
+}
+impl_rcin_read!(RInStream, source);
+
+/// Global stream locked by `rin.lock()`, other users wait until it's dropped
+///
+/// ``` rust
+///     use rcin::{rin, RcinRead};
+///
+///     rin.set_source(Box::new(&b"7 x"[..]));
+///     let mut lock = rin.lock();
+///     assert_eq!(lock.read::<i32>(), Some(7));
+///     assert_eq!(lock.peek_token(), Some("x"));
+/// ```
+#[cfg(feature = "std")]
+pub struct RCinLock {
+    guard: GlobGuard,
+}
+#[cfg(feature = "std")]
+impl_rcin_read!(RCinLock, guard);
+
+/// Bounded view of a [`RInStream`], created by `take_tokens` and `take_lines`
+pub struct Take<'a> {
+    stream: &'a mut RInStream,
+}
+impl core::ops::Deref for Take<'_> {
+    type Target = RInStream;
+    fn deref(&self) -> &RInStream {
+        self.stream
+    }
+}
+impl core::ops::DerefMut for Take<'_> {
+    fn deref_mut(&mut self) -> &mut RInStream {
+        self.stream
+    }
+}
+impl Drop for Take<'_> {
+    fn drop(&mut self) {
+        self.stream.source.budgets.pop();
+    }
+}
+
+/// Iterator over CSV records of a [`RInStream`]
+pub struct CsvRecords<'a> {
+    stream: &'a mut RInStream,
+    delimiter: char,
+}
+impl Iterator for CsvRecords<'_> {
+    type Item = Vec<String>;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.stream.read_csv_record(self.delimiter)
+    }
+}
+
+/// Iterator over events of a [`RInStream`]
+pub struct Events<'a> {
+    stream: &'a mut RInStream,
+    in_token: bool,
+    pending: Option<Event>,
+    failed: bool, // the source returned an error, nothing follows it
+}
+impl Iterator for Events<'_> {
+    type Item = Result<Event, ReadError>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(event) = self.pending.take() {
+            return Some(Ok(event));
+        }
+        if self.failed {
+            return None;
+        }
+        let stream = &mut self.stream.source;
+        loop {
+            let at = stream.pos;
+            let c = match stream.next_char_result() {
+                Ok(Some(c)) => c,
+                Err(DecodeError::InvalidUtf8(at)) => return Some(Ok(Event::DecodeError(at))),
+                Err(error) => {
+                    self.failed = true;
+                    return Some(Err(ReadError::Decode(error)));
+                }
+                Ok(None) if self.in_token => {
+                    self.in_token = false;
+                    return Some(Ok(Event::TokenEnd(at)));
+                }
+                Ok(None) => return None,
+            };
+            let newline = (c == '\n').then_some(Event::Newline(at));
+            if !stream.whitespace.contains(c) {
+                if !self.in_token {
+                    self.in_token = true;
+                    return Some(Ok(Event::TokenStart(at)));
+                }
+            } else if self.in_token {
+                self.in_token = false;
+                self.pending = newline;
+                return Some(Ok(Event::TokenEnd(at)));
+            } else if newline.is_some() {
+                return newline.map(Ok);
+            }
+        }
+    }
+}
+
+/// Iterator over tokens of a logos lexer run over a [`RInStream`], the unlexed rest is returned to the stream on drop
+#[cfg(feature = "logos")]
+pub struct LogosTokens<'a, T> {
+    stream: &'a mut RInStream,
+    buf: String,
+    used: usize,     // bytes of buf already lexed
+    at: Position,    // position of buf[used]
+    last: usize,     // start of the last token in buf
+    eof: bool,
+    marker: core::marker::PhantomData<T>,
+}
+#[cfg(feature = "logos")]
+impl<T> LogosTokens<'_, T>
+where
+    T: for<'s> logos::Logos<'s, Source = str, Extras = ()>,
+{
+    /// Next token, lexing errors fail with `ReadError::Parse` of the invalid text
+    ///
+    /// A token that reaches the end of the buffered input is only returned once more input confirms it's complete
+    pub fn next_token(&mut self) -> Option<Result<T, ReadError>> {
+        loop {
+            let rest = &self.buf[self.used..];
+            let mut lexer = T::lexer(rest);
+            let next = lexer.next().map(|res| res.map_err(|_| ()));
+            let span = lexer.span();
+            match next {
+                Some(res) if span.end < rest.len() || self.eof => {
+                    self.last = self.used + span.start;
+                    self.at = rest[..span.end].chars().fold(self.at, Position::advance);
+                    self.used += span.end;
+                    let slice = &self.buf[self.last..self.used];
+                    return Some(res.map_err(|_| ReadError::Parse(String::from(slice))));
+                }
+                None if self.eof => {
+                    self.at = rest.chars().fold(self.at, Position::advance);
+                    self.used = self.buf.len();
+                    return None;
+                }
+                _ => {
+                    self.buf.drain(..self.used);
+                    self.last = 0;
+                    self.used = 0;
+                    match self.stream.source.read_until_raw('\n') {
+                        Ok((line, found)) => {
+                            self.eof = !found && line.is_empty() && !self.stream.valid();
+                            self.buf.push_str(&line);
+                            if found {
+                                self.buf.push('\n');
+                            }
+                        }
+                        Err(err) => return Some(Err(err)),
+                    }
+                }
+            }
+        }
+    }
+    /// Text of the last token
+    pub fn slice(&self) -> &str {
+        &self.buf[self.last..self.used]
+    }
+}
+#[cfg(feature = "logos")]
+impl<T> Iterator for LogosTokens<'_, T>
+where
+    T: for<'s> logos::Logos<'s, Source = str, Extras = ()>,
+{
+    type Item = Result<T, ReadError>;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_token()
+    }
+}
+#[cfg(feature = "logos")]
+impl<T> Drop for LogosTokens<'_, T> {
+    fn drop(&mut self) {
+        self.stream.source.unread(&self.buf[self.used..], self.at);
+    }
 }
 
+/// Iterator over json lines of a [`RInStream`]
+#[cfg(feature = "serde_json")]
+pub struct JsonLines<'a, T> {
+    stream: &'a mut RInStream,
+    marker: core::marker::PhantomData<T>,
+}
+#[cfg(feature = "serde_json")]
+impl<T: serde::de::DeserializeOwned> Iterator for JsonLines<'_, T> {
+    type Item = T;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.stream.read_json()
+    }
+}
@@ -0,0 +1,64 @@
+//!
+//! Macros
+//!
+
+/// Define an enum whose `FromStr` matches variant names and aliases case-insensitively
+///
+/// ``` rust
+///     use rcin::{token_enum, RInStream};
+///
+///     token_enum! {
+///         #[derive(Debug, PartialEq)]
+///         enum Command {
+///             Start | "go" | "run",
+///             Stop,
+///         }
+///     }
+///
+///     let mut reader = RInStream::from_source(Box::new(&b"START Go stop"[..]));
+///     let commands: Vec<Command> = reader.read_all();
+///     assert_eq!(commands, vec![Command::Start, Command::Start, Command::Stop]);
+/// ```
+#[macro_export]
+macro_rules! token_enum {
+    (
+        $(#[$meta:meta])*
+        $vis:vis enum $name:ident {
+            $($(#[$vmeta:meta])* $variant:ident $(| $alias:literal)*),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        $vis enum $name {
+            $($(#[$vmeta])* $variant),*
+        }
+        impl ::core::str::FromStr for $name {
+            type Err = ();
+            fn from_str(s: &str) -> ::core::result::Result<Self, ()> {
+                $(
+                    if s.eq_ignore_ascii_case(stringify!($variant)) $(|| s.eq_ignore_ascii_case($alias))* {
+                        return Ok($name::$variant);
+                    }
+                )*
+                Err(())
+            }
+        }
+    };
+}
+
+/// Print a formatted prompt to stdout, flush it and read the reply from `rin`
+///
+/// ``` rust
+///     use rcin::{ask, rin};
+///
+///     rin.set_source(Box::new(&b"49"[..]));
+///     let y = 7;
+///     let x: Option<i32> = ask!("{} * {} = ? ", y, y);
+///     assert_eq!(x, Some(49));
+/// ```
+#[cfg(feature = "std")]
+#[macro_export]
+macro_rules! ask {
+    ($($arg:tt)*) => {
+        $crate::rin.ask(::core::format_args!($($arg)*))
+    };
+}
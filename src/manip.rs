@@ -0,0 +1,77 @@
+//!
+//! Manipulators change how a single value is parsed, like `std::hex` in C++
+//!
+//! ``` rust
+//!     use rcin::RInStream;
+//!     use rcin::manip::{Grouped, Radix};
+//!
+//!     let mut reader = RInStream::from_source(Box::new(&b"ff 0x10 1,000,000 z"[..]));
+//!     assert_eq!(reader.read_with::<u32>(Radix(16)), Some(255));
+//!     assert_eq!(reader.read_with::<u32>(Radix(16)), Some(16));
+//!     assert_eq!(reader.read_with::<u64>(Grouped(',')), Some(1_000_000));
+//!     assert_eq!(reader.read_with::<u32>(Radix(40)), None);
+//!     assert_eq!(reader.last_failure().unwrap().token.as_deref(), Some("z"));
+//! ```
+//!
+
+use core::str::FromStr;
+#[cfg(not(feature = "std"))]
+use alloc::string::String;
+
+/// Parses a token into T instead of `FromStr`
+pub trait Manipulator<T> {
+    /// Parse the token, None if it's invalid
+    fn parse(&self, token: &str) -> Option<T>;
+}
+
+/// Integer in the given radix, a matching `0x`, `0o` or `0b` prefix is allowed
+///
+/// Radixes outside of 2..=36 don't parse anything
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Radix(pub u32);
+
+macro_rules! impl_radix {
+    ($($t:ty)*) => {$(
+        impl Manipulator<$t> for Radix {
+            fn parse(&self, token: &str) -> Option<$t> {
+                if !(2..=36).contains(&self.0) {
+                    return None;
+                }
+                let (negative, digits) = match token.strip_prefix('-') {
+                    Some(rest) => (true, rest),
+                    None => (false, token.strip_prefix('+').unwrap_or(token)),
+                };
+                let prefix = match self.0 {
+                    16 => Some("0x"),
+                    8 => Some("0o"),
+                    2 => Some("0b"),
+                    _ => None,
+                };
+                let digits = prefix.and_then(|p| digits.strip_prefix(p)).unwrap_or(digits);
+                if digits.starts_with(['+', '-']) {
+                    return None;
+                }
+                if negative {
+                    let mut signed = String::with_capacity(digits.len() + 1);
+                    signed.push('-');
+                    signed.push_str(digits);
+                    <$t>::from_str_radix(&signed, self.0).ok()
+                } else {
+                    <$t>::from_str_radix(digits, self.0).ok()
+                }
+            }
+        }
+    )*};
+}
+impl_radix!(u8 u16 u32 u64 u128 usize i8 i16 i32 i64 i128 isize);
+
+/// Number with digit groups split by the separator, like `1,000,000`
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Grouped(pub char);
+
+impl<T: FromStr> Manipulator<T> for Grouped {
+    fn parse(&self, token: &str) -> Option<T> {
+        let plain: String = token.chars().filter(|&c| c != self.0).collect();
+        plain.parse().ok()
+    }
+}
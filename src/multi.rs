@@ -0,0 +1,288 @@
+//!
+//! Multiplexing tokens of several streams by waiting for their sources to become readable
+//!
+
+use crate::{RInStream, Stream};
+use std::io::Read;
+
+/// File descriptor waited on with `poll`
+#[cfg(unix)]
+pub(crate) type WaitHandle = std::os::unix::io::RawFd;
+/// HANDLE waited on with `WaitForMultipleObjects`, kept as an integer so streams stay Send
+#[cfg(windows)]
+pub(crate) type WaitHandle = usize;
+#[cfg(not(any(unix, windows)))]
+pub(crate) type WaitHandle = ();
+
+#[cfg(unix)]
+pub(crate) fn handle_of(src: &impl std::os::unix::io::AsRawFd) -> WaitHandle {
+    src.as_raw_fd()
+}
+#[cfg(windows)]
+pub(crate) fn handle_of(src: &impl std::os::windows::io::AsRawHandle) -> WaitHandle {
+    src.as_raw_handle() as usize
+}
+
+impl RInStream {
+    /// Create new stream from a source a [`MultiStream`] can wait on, like a pipe, socket or FIFO
+    ///
+    /// Pass an unbuffered source: bytes a reader like `io::stdin()` buffered internally aren't seen
+    /// by the wait. Stdin can be read with `File::from(io::stdin().as_fd().try_clone_to_owned()?)`
+    ///
+    /// ``` rust
+    ///     use std::io::Write;
+    ///     use std::os::unix::net::UnixStream;
+    ///     use rcin::{MultiStream, RInStream};
+    ///
+    ///     let (mut tx, rx) = UnixStream::pair().unwrap();
+    ///     let mut multi = MultiStream::new(vec![RInStream::from_pollable(rx)]);
+    ///     tx.write_all(b"ready pending").unwrap();
+    ///     // "pending" can continue, so only the first token is returned
+    ///     assert_eq!(multi.try_next_ready(), Some((0, "ready".to_string())));
+    ///     assert_eq!(multi.try_next_ready(), None);
+    ///     drop(tx);
+    ///     assert_eq!(multi.next_ready(), Some((0, "pending".to_string())));
+    ///     assert_eq!(multi.next_ready(), None);
+    /// ```
+    #[cfg(unix)]
+    pub fn from_pollable<R>(src: R) -> Self
+    where
+        R: Read + std::os::unix::io::AsRawFd + Send + 'static,
+    {
+        let handle = handle_of(&src);
+        let mut stream = Self::from_source(Box::new(src));
+        stream.source.wait_handle = Some(handle);
+        stream
+    }
+    /// Create new stream from a source a [`MultiStream`] can wait on, like a console or a process
+    ///
+    /// Pass an unbuffered source: bytes a reader like `io::stdin()` buffered internally aren't seen
+    /// by the wait. Stdin can be read with `File::from(io::stdin().as_handle().try_clone_to_owned()?)`
+    #[cfg(windows)]
+    pub fn from_pollable<R>(src: R) -> Self
+    where
+        R: Read + std::os::windows::io::AsRawHandle + Send + 'static,
+    {
+        let handle = handle_of(&src);
+        let mut stream = Self::from_source(Box::new(src));
+        stream.source.wait_handle = Some(handle);
+        stream
+    }
+}
+
+/// Owns several streams and returns tokens from whichever has one ready
+///
+/// Sources of streams created with `from_pollable` or `from_file` are only read once `poll`
+/// (`WaitForMultipleObjects` on Windows) reports them readable, and a token is only returned once
+/// the whitespace after it or the end of input is buffered, so a stream waiting for input doesn't
+/// hold back the others. Other sources are taken to never block. Streams are served in turn.
+///
+/// A token longer than the buffer of its stream is read with blocking reads.
+///
+/// ``` rust
+///     use rcin::{MultiStream, RInStream};
+///
+///     let mut multi = MultiStream::new(vec![
+///         RInStream::from_source(Box::new(&b"a b"[..])),
+///         RInStream::from_source(Box::new(&b"c"[..])),
+///     ]);
+///     let mut tokens = Vec::new();
+///     while let Some((idx, token)) = multi.next_ready() {
+///         tokens.push((idx, token));
+///     }
+///     tokens.sort();
+///     assert_eq!(tokens, vec![(0, "a".into()), (0, "b".into()), (1, "c".into())]);
+/// ```
+pub struct MultiStream {
+    streams: Vec<RInStream>,
+    ended: Vec<bool>,   // returned None, not read again
+    drained: Vec<bool>, // the last read of the source returned nothing, reading doesn't wait for it
+    next: usize,        // stream served first by the next read
+    finished: bool,
+}
+impl MultiStream {
+    /// Take all streams, indices of returned tokens match the order of streams
+    pub fn new(streams: Vec<RInStream>) -> Self {
+        let n = streams.len();
+        MultiStream {
+            streams,
+            ended: vec![false; n],
+            drained: vec![false; n],
+            next: 0,
+            finished: false,
+        }
+    }
+    /// Wait for the next token of any stream, None when all streams ended
+    pub fn next_ready(&mut self) -> Option<(usize, String)> {
+        self.read_ready(true)
+    }
+    /// Return a token if one is ready without waiting
+    ///
+    /// None if no stream has a token yet, use `finished` to tell it apart from the end of all streams
+    pub fn try_next_ready(&mut self) -> Option<(usize, String)> {
+        self.read_ready(false)
+    }
+    /// True once a read found that all streams ended and all their tokens were returned
+    pub fn finished(&self) -> bool {
+        self.finished
+    }
+    fn read_ready(&mut self, block: bool) -> Option<(usize, String)> {
+        loop {
+            if let Some(res) = self.take_buffered() {
+                return Some(res);
+            }
+            let open: Vec<usize> = (0..self.streams.len()).filter(|&idx| !self.ended[idx]).collect();
+            if open.is_empty() {
+                self.finished = true;
+                return None;
+            }
+            let ready = self.wait(&open, block);
+            if ready.is_empty() {
+                return None;
+            }
+            for idx in ready {
+                if !self.streams[idx].source.fill_once() {
+                    self.drained[idx] = true;
+                }
+            }
+        }
+    }
+    // a token of the next stream in turn that doesn't wait for its source
+    fn take_buffered(&mut self) -> Option<(usize, String)> {
+        let (n, first) = (self.streams.len(), self.next);
+        for idx in (0..n).map(|k| (first + k) % n) {
+            if self.ended[idx] || !(self.drained[idx] || self.streams[idx].source.token_buffered()) {
+                continue;
+            }
+            self.drained[idx] = false;
+            match self.streams[idx].read::<String>() {
+                Some(token) => {
+                    self.next = idx + 1;
+                    return Some((idx, token));
+                }
+                None => self.ended[idx] = true,
+            }
+        }
+        None
+    }
+    // open streams whose source can be read without blocking, sources without a handle always can
+    fn wait(&self, open: &[usize], block: bool) -> Vec<usize> {
+        let mut ready = Vec::new();
+        let mut waited = Vec::new();
+        let mut handles = Vec::new();
+        for &idx in open {
+            match self.streams[idx].source.wait_handle {
+                Some(handle) => {
+                    waited.push(idx);
+                    handles.push(handle);
+                }
+                None => ready.push(idx),
+            }
+        }
+        if handles.is_empty() {
+            return ready;
+        }
+        match wait_handles(&handles, block && ready.is_empty()) {
+            Ok(found) => ready.extend(found.into_iter().map(|i| waited[i])),
+            // reading doesn't hang on a failed wait, it blocks like a single stream
+            Err(_) => ready.extend(waited),
+        }
+        ready
+    }
+}
+
+impl Stream {
+    // a whole token is buffered, so reading it doesn't touch the source
+    fn token_buffered(&self) -> bool {
+        let bytes = &self.buf[self.ptr..self.limit];
+        let text = match core::str::from_utf8(bytes) {
+            Ok(text) => text,
+            // invalid bytes end the read, an incomplete char at the end waits for the rest
+            Err(e) if e.error_len().is_some() => return true,
+            Err(e) => core::str::from_utf8(&bytes[..e.valid_up_to()]).unwrap_or_default(),
+        };
+        let ws = |c: char| self.whitespace.contains(c);
+        let mut chars = self.pushback.iter().rev().map(|&(c, _)| c).chain(text.chars());
+        let first = if self.skipws { chars.find(|&c| !ws(c)) } else { chars.next() };
+        match first {
+            None => false,
+            Some(c) if ws(c) => true,
+            Some(_) => chars.any(ws),
+        }
+    }
+    // read the source once, false if it returned nothing or the buffer is full
+    fn fill_once(&mut self) -> bool {
+        let before = self.limit - self.ptr;
+        self.refill();
+        self.limit - self.ptr > before
+    }
+}
+
+// indices of the readable handles, none if nothing is readable and block is false
+#[cfg(unix)]
+fn wait_handles(handles: &[WaitHandle], block: bool) -> std::io::Result<Vec<usize>> {
+    use std::os::raw::{c_int, c_short};
+    #[cfg(target_os = "linux")]
+    type NFds = std::os::raw::c_ulong;
+    #[cfg(not(target_os = "linux"))]
+    type NFds = std::os::raw::c_uint;
+    #[repr(C)]
+    struct PollFd {
+        fd: c_int,
+        events: c_short,
+        revents: c_short,
+    }
+    extern "C" {
+        fn poll(fds: *mut PollFd, nfds: NFds, timeout: c_int) -> c_int;
+    }
+    const POLLIN: c_short = 1;
+    let mut fds: Vec<PollFd> = handles
+        .iter()
+        .map(|&fd| PollFd {
+            fd,
+            events: POLLIN,
+            revents: 0,
+        })
+        .collect();
+    loop {
+        // safety: fds is a valid array of fds.len() entries
+        let res = unsafe { poll(fds.as_mut_ptr(), fds.len() as NFds, if block { -1 } else { 0 }) };
+        if res >= 0 {
+            break;
+        }
+        let e = std::io::Error::last_os_error();
+        if e.kind() != std::io::ErrorKind::Interrupted {
+            return Err(e);
+        }
+    }
+    // a hang up or an error is readable too, the read returns it right away
+    Ok(fds.iter().enumerate().filter(|(_, fd)| fd.revents != 0).map(|(i, _)| i).collect())
+}
+
+#[cfg(windows)]
+fn wait_handles(handles: &[WaitHandle], block: bool) -> std::io::Result<Vec<usize>> {
+    use core::ffi::c_void;
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn WaitForMultipleObjects(count: u32, handles: *const *mut c_void, wait_all: i32, millis: u32) -> u32;
+    }
+    const MAXIMUM_WAIT_OBJECTS: usize = 64;
+    const WAIT_TIMEOUT: u32 = 0x102;
+    const WAIT_FAILED: u32 = 0xFFFF_FFFF;
+    const INFINITE: u32 = 0xFFFF_FFFF;
+    // handles past the limit aren't waited on, they're served once the first ones end
+    let raw: Vec<*mut c_void> = handles.iter().take(MAXIMUM_WAIT_OBJECTS).map(|&h| h as *mut c_void).collect();
+    // safety: raw is a valid array of raw.len() handles
+    let res = unsafe { WaitForMultipleObjects(raw.len() as u32, raw.as_ptr(), 0, if block { INFINITE } else { 0 }) };
+    match res {
+        WAIT_TIMEOUT => Ok(Vec::new()),
+        WAIT_FAILED => Err(std::io::Error::last_os_error()),
+        // the lowest signaled handle, or an abandoned one
+        res => Ok(vec![(res as usize % 0x80).min(raw.len() - 1)]),
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn wait_handles(handles: &[WaitHandle], _block: bool) -> std::io::Result<Vec<usize>> {
+    Ok((0..handles.len()).collect())
+}
@@ -0,0 +1,143 @@
+//!
+//! Helpers for parsing one file from multiple threads
+//!
+//! ``` rust
+//!     use std::thread;
+//!     use rcin::par;
+//!
+//!     let streams = par::split_file("test.txt", 4).unwrap();
+//!     let handles: Vec<_> = streams
+//!         .into_iter()
+//!         .map(|mut s| thread::spawn(move || s.read_all_lines().len()))
+//!         .collect();
+//!     let lines: usize = handles.into_iter().map(|h| h.join().unwrap()).sum();
+//!     assert_eq!(lines, 2);
+//! ```
+//!
+
+use crate::RInStream;
+#[cfg(all(feature = "rayon", not(target_family = "wasm")))]
+use rayon::iter::{ParallelBridge, ParallelIterator};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+#[cfg(all(feature = "rayon", not(target_family = "wasm")))]
+use std::sync::mpsc;
+#[cfg(all(feature = "rayon", not(target_family = "wasm")))]
+use std::thread;
+
+/// Number of items the reader thread can read ahead of the rayon pool
+#[cfg(all(feature = "rayon", not(target_family = "wasm")))]
+pub const READ_AHEAD: usize = 1024;
+
+/// Split the file into n streams of about the same size, every chunk starts at the beginning of a line
+///
+/// Small files can result in empty streams, positions of every stream are relative to the whole file
+///
+/// ``` rust
+///     use rcin::par;
+///
+///     let mut streams = par::split_file("test.txt", 3).unwrap();
+///     assert_eq!(streams[1].read_line().as_deref(), Some("READ ME NEXT"));
+///     assert_eq!(streams[1].position().line, 2);
+/// ```
+pub fn split_file<P: AsRef<Path>>(path: P, n: usize) -> io::Result<Vec<RInStream>> {
+    let path = path.as_ref();
+    let len = File::open(path)?.metadata()?.len();
+    let n = n.max(1) as u64;
+    let mut bounds = vec![0];
+    for i in 1..n {
+        let prev = *bounds.last().unwrap();
+        bounds.push(line_start(path, len * i / n)?.max(prev));
+    }
+    bounds.push(len);
+    let lines = lines_before(path, &bounds)?;
+    bounds
+        .windows(2)
+        .zip(lines)
+        .map(|(w, line)| {
+            let mut f = File::open(path)?;
+            f.seek(SeekFrom::Start(w[0]))?;
+            let mut stream = RInStream::from_source(Box::new(f.take(w[1] - w[0])));
+            stream.source.consumed = w[0] as usize;
+            stream.source.pos.offset = w[0] as usize;
+            stream.source.pos.line = line;
+            stream.source.total_size = Some(w[1] - w[0]);
+            Ok(stream)
+        })
+        .collect()
+}
+
+// line number at each of the sorted offsets, counted in one pass over the file
+fn lines_before(path: &Path, offsets: &[u64]) -> io::Result<Vec<usize>> {
+    let mut f = BufReader::new(File::open(path)?);
+    let mut lines = Vec::with_capacity(offsets.len());
+    let (mut at, mut line) = (0, 1);
+    for &offset in offsets {
+        while at < offset {
+            let buf = f.fill_buf()?;
+            if buf.is_empty() {
+                break;
+            }
+            let n = buf.len().min((offset - at) as usize);
+            line += buf[..n].iter().filter(|&&b| b == b'\n').count();
+            f.consume(n);
+            at += n as u64;
+        }
+        lines.push(line);
+    }
+    Ok(lines)
+}
+
+// first line start at or after offset
+fn line_start(path: &Path, offset: u64) -> io::Result<u64> {
+    if offset == 0 {
+        return Ok(0);
+    }
+    let mut f = File::open(path)?;
+    f.seek(SeekFrom::Start(offset - 1))?;
+    let skipped = BufReader::new(f).read_until(b'\n', &mut Vec::new())?;
+    Ok(offset - 1 + skipped as u64)
+}
+
+#[cfg(all(feature = "rayon", not(target_family = "wasm")))]
+impl RInStream {
+    /// Read lines on a background thread and process them on the rayon pool
+    ///
+    /// At most `READ_AHEAD` lines are buffered, so reading and processing overlap without
+    /// loading the whole input. The order of lines isn't preserved
+    ///
+    /// ``` rust
+    ///     use rayon::prelude::*;
+    ///     use rcin::RInStream;
+    ///
+    ///     let reader = RInStream::from_source(Box::new(&b"1\n2\n3\n"[..]));
+    ///     let sum: i64 = reader.par_lines().map(|l| l.parse::<i64>().unwrap()).sum();
+    ///     assert_eq!(sum, 6);
+    /// ```
+    pub fn par_lines(mut self) -> impl ParallelIterator<Item = String> {
+        pump(move || self.read_line())
+    }
+    /// Read CSV records on a background thread and process them on the rayon pool
+    pub fn par_records(mut self, delimiter: char) -> impl ParallelIterator<Item = Vec<String>> {
+        pump(move || self.read_csv_record(delimiter))
+    }
+}
+
+// move items produced by next to a bounded channel on a separate thread
+#[cfg(all(feature = "rayon", not(target_family = "wasm")))]
+fn pump<T, F>(mut next: F) -> impl ParallelIterator<Item = T>
+where
+    T: Send + 'static,
+    F: FnMut() -> Option<T> + Send + 'static,
+{
+    let (tx, rx) = mpsc::sync_channel(READ_AHEAD);
+    thread::spawn(move || {
+        while let Some(item) = next() {
+            if tx.send(item).is_err() {
+                break; // the iterator was dropped
+            }
+        }
+    });
+    rx.into_iter().par_bridge()
+}
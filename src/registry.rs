@@ -0,0 +1,51 @@
+//!
+//! Globally accessible named streams
+//!
+
+use crate::{lock_stream, RInStream};
+use std::collections::HashMap;
+use std::io::Read;
+use std::sync::{Mutex, MutexGuard, OnceLock};
+
+// streams live as long as the program, like rin
+fn streams() -> &'static Mutex<HashMap<String, &'static Mutex<RInStream>>> {
+    static STREAMS: OnceLock<Mutex<HashMap<String, &'static Mutex<RInStream>>>> = OnceLock::new();
+    STREAMS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Make a source readable from anywhere with `stream(name)`
+///
+/// Registering a name again resets its stream to the new source
+///
+/// ``` rust
+///     rcin::register("config", Box::new(&b"threads 4"[..]));
+///
+///     fn threads() -> Option<u32> {
+///         let mut config = rcin::stream("config")?;
+///         config.read::<String>()?;
+///         config.read()
+///     }
+///     assert_eq!(threads(), Some(4));
+/// ```
+pub fn register(name: &str, src: Box<dyn Read + Send>) {
+    let mut map = streams().lock().unwrap_or_else(|e| e.into_inner());
+    let stream = match map.get(name) {
+        Some(&stream) => stream,
+        None => {
+            let stream = Box::leak(Box::new(Mutex::new(RInStream::from_source(src))));
+            map.insert(name.to_string(), stream);
+            return;
+        }
+    };
+    // a guard of the stream can be held by a thread waiting for the map
+    drop(map);
+    lock_stream(stream).reset(src);
+}
+
+/// Lock the stream registered under name, None if there is none
+///
+/// The stream stays locked for other threads as long as the guard lives
+pub fn stream(name: &str) -> Option<MutexGuard<'static, RInStream>> {
+    let stream = *streams().lock().unwrap_or_else(|e| e.into_inner()).get(name)?;
+    Some(lock_stream(stream))
+}
@@ -0,0 +1,300 @@
+//!
+//! Parallel prescan of a file, to size structures before it's parsed
+//!
+
+use crate::{sniff_encoding, Encoding, LineEnding};
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::thread;
+
+const CHUNK_SIZE: usize = 1 << 20;
+const MIN_RANGE: u64 = 4 << 20; // bytes scanned by one thread at least
+const SAMPLE_SIZE: usize = 4096; // bytes looked at to guess the encoding
+
+/// Summary of a file computed by [`analyze`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct FileStats {
+    /// Size in bytes
+    pub size: u64,
+    /// Number of lines, the last one doesn't need a line break
+    pub lines: u64,
+    /// Length of the longest line without the line break, in bytes or in utf16 units
+    pub max_line_len: u64,
+    /// Most likely encoding
+    pub encoding: Encoding,
+    /// Line breaks in the file
+    pub line_ending: LineEnding,
+}
+
+/// Count lines and guess the encoding and line breaks of a file, large files are split between threads
+///
+/// With the `mmap` feature the file is memory mapped and the threads scan the map, otherwise
+/// every thread reads its range with buffered reads
+///
+/// ``` rust
+///     use rcin::{analyze, Encoding, LineEnding};
+///
+///     let path = std::env::temp_dir().join("rcin_analyze_doc.txt");
+///     std::fs::write(&path, "id,name\r\n1,zoë\r\n2,longer name").unwrap();
+///     let stats = analyze(&path).unwrap();
+///     assert_eq!(stats.lines, 3);
+///     assert_eq!(stats.max_line_len, 13);
+///     assert_eq!(stats.encoding, Encoding::Utf8);
+///     assert_eq!(stats.line_ending, LineEnding::CrLf);
+/// ```
+pub fn analyze(path: impl AsRef<Path>) -> io::Result<FileStats> {
+    let path = path.as_ref();
+    let mut file = File::open(path)?;
+    let size = file.metadata()?.len();
+    let mut sample = vec![0; SAMPLE_SIZE];
+    let n = read_full(&mut file, &mut sample)?;
+    let (sniffed, bom) = match sniff_encoding(&sample[..n]) {
+        Some((encoding, bom)) => (Some(encoding), bom as u64),
+        None => (None, 0),
+    };
+    let wide = match sniffed {
+        Some(Encoding::Utf16Le) => Some(false),
+        Some(Encoding::Utf16Be) => Some(true),
+        _ => None,
+    };
+    let threads = thread::available_parallelism().map_or(1, |n| n.get() as u64);
+    let ranges = threads.min(size / MIN_RANGE).max(1);
+    let bounds: Vec<u64> = (0..=ranges)
+        .map(|i| match i {
+            0 => bom,
+            i if i == ranges => size,
+            // even, so utf16 units aren't split
+            i => (bom + (size - bom) * i / ranges) & !1,
+        })
+        .collect();
+    #[cfg(feature = "mmap")]
+    // safety: the map is only read, a file changed meanwhile gives wrong stats like a racing read would
+    let map = match size {
+        0 => None,
+        _ => Some(unsafe { memmap2::Mmap::map(&file)? }),
+    };
+    let partials = thread::scope(|s| {
+        let handles: Vec<_> = bounds
+            .windows(2)
+            .map(|range| {
+                let (start, end) = (range[0], range[1]);
+                #[cfg(feature = "mmap")]
+                if let Some(map) = &map {
+                    let bytes = &map[start as usize..end as usize];
+                    return s.spawn(move || Ok(scan_bytes(bytes.chunks(CHUNK_SIZE), wide)));
+                }
+                s.spawn(move || scan_range(path, start, end, wide))
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|h| h.join().unwrap_or_else(|_| Err(io::Error::other("scan thread panicked"))))
+            .collect::<io::Result<Vec<_>>>()
+    })?;
+    let total = partials.into_iter().fold(Partial::empty(), Partial::merge);
+    let newlines = total.lf + total.crlf;
+    let max_line_len = match total.first {
+        Some(first) => first.max(total.max).max(total.last),
+        None => total.last,
+    };
+    let encoding = match sniffed {
+        Some(encoding) => encoding,
+        None if total.utf8 && total.head.is_empty() && total.tail.is_empty() => Encoding::Utf8,
+        None => Encoding::Latin1,
+    };
+    let line_ending = match (total.lf, total.crlf) {
+        (0, 0) => LineEnding::Unknown,
+        (_, 0) => LineEnding::Lf,
+        (0, _) => LineEnding::CrLf,
+        _ => LineEnding::Mixed,
+    };
+    Ok(FileStats {
+        size,
+        lines: newlines + u64::from(total.last > 0),
+        max_line_len,
+        encoding,
+        line_ending,
+    })
+}
+
+// stats of a range of the file, merged with the ranges after it
+struct Partial {
+    units: u64,
+    lf: u64,
+    crlf: u64,
+    first: Option<u64>, // length of the line cut by the start, None without a line break
+    max: u64,           // longest line inside the range
+    last: u64,          // units after the last line break
+    starts_lf: bool,
+    ends_cr: bool,
+    head: Vec<u8>, // utf8 continuation bytes at the start
+    tail: Vec<u8>, // incomplete utf8 sequence at the end
+    utf8: bool,
+}
+
+impl Partial {
+    fn empty() -> Self {
+        Partial {
+            units: 0,
+            lf: 0,
+            crlf: 0,
+            first: None,
+            max: 0,
+            last: 0,
+            starts_lf: false,
+            ends_cr: false,
+            head: Vec::new(),
+            tail: Vec::new(),
+            utf8: true,
+        }
+    }
+    fn merge(self, next: Partial) -> Partial {
+        if self.units == 0 {
+            return next;
+        }
+        if next.units == 0 {
+            return self;
+        }
+        // a \r\n split between the ranges was counted as \n
+        let split_crlf = self.ends_cr && next.starts_lf;
+        let joined = next.first.map(|first| self.last + first - u64::from(split_crlf));
+        let seam = [&self.tail[..], &next.head[..]].concat();
+        Partial {
+            units: self.units + next.units,
+            lf: self.lf + next.lf - u64::from(split_crlf),
+            crlf: self.crlf + next.crlf + u64::from(split_crlf),
+            first: self.first.or(joined),
+            max: match self.first {
+                Some(_) => self.max.max(next.max).max(joined.unwrap_or(0)),
+                None => next.max,
+            },
+            last: match next.first {
+                Some(_) => next.last,
+                None => self.last + next.last,
+            },
+            starts_lf: self.starts_lf,
+            ends_cr: next.ends_cr,
+            head: self.head,
+            tail: next.tail,
+            utf8: self.utf8 && next.utf8 && core::str::from_utf8(&seam).is_ok(),
+        }
+    }
+}
+
+// wide is Some(big endian) for utf16
+fn scan_range(path: &Path, start: u64, end: u64, wide: Option<bool>) -> io::Result<Partial> {
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start(start))?;
+    let mut file = file.take(end - start);
+    let mut scanner = Scanner::new(wide);
+    let mut chunk = vec![0; CHUNK_SIZE];
+    loop {
+        let n = read_full(&mut file, &mut chunk)?;
+        if n == 0 {
+            break;
+        }
+        scanner.feed(&chunk[..n]);
+    }
+    Ok(scanner.finish())
+}
+
+#[cfg(feature = "mmap")]
+fn scan_bytes<'a>(chunks: impl Iterator<Item = &'a [u8]>, wide: Option<bool>) -> Partial {
+    let mut scanner = Scanner::new(wide);
+    chunks.for_each(|chunk| scanner.feed(chunk));
+    scanner.finish()
+}
+
+// stats of a range fed chunk by chunk
+struct Scanner {
+    part: Partial,
+    wide: Option<bool>,
+    prev: Option<u16>,
+    len: u64, // units since the last line break
+    first_chunk: bool,
+}
+
+impl Scanner {
+    fn new(wide: Option<bool>) -> Self {
+        Scanner {
+            part: Partial::empty(),
+            wide,
+            prev: None,
+            len: 0,
+            first_chunk: true,
+        }
+    }
+    fn feed(&mut self, bytes: &[u8]) {
+        match self.wide {
+            Some(big) => bytes.chunks_exact(2).for_each(|pair| {
+                let pair = [pair[0], pair[1]];
+                self.unit(if big { u16::from_be_bytes(pair) } else { u16::from_le_bytes(pair) })
+            }),
+            None => bytes.iter().for_each(|&b| self.unit(u16::from(b))),
+        }
+        if self.wide.is_none() && self.part.utf8 {
+            check_utf8(&mut self.part, bytes, self.first_chunk);
+        }
+        self.first_chunk = false;
+    }
+    fn unit(&mut self, unit: u16) {
+        let part = &mut self.part;
+        if unit == u16::from(b'\n') {
+            let cr = self.prev == Some(u16::from(b'\r'));
+            if cr {
+                part.crlf += 1;
+            } else {
+                part.lf += 1;
+            }
+            part.starts_lf |= self.prev.is_none();
+            let line = self.len - u64::from(cr);
+            match part.first {
+                None => part.first = Some(line),
+                Some(_) => part.max = part.max.max(line),
+            }
+            self.len = 0;
+        } else {
+            self.len += 1;
+        }
+        self.prev = Some(unit);
+        part.units += 1;
+    }
+    fn finish(mut self) -> Partial {
+        self.part.last = self.len;
+        self.part.ends_cr = self.prev == Some(u16::from(b'\r'));
+        self.part
+    }
+}
+
+// validate bytes after the incomplete sequence of the previous chunk
+fn check_utf8(part: &mut Partial, bytes: &[u8], first_chunk: bool) {
+    let mut bytes = bytes;
+    if first_chunk {
+        // a char can start in the range before
+        let cut = bytes.iter().take(3).take_while(|&&b| b & 0xC0 == 0x80).count();
+        part.head = bytes[..cut].to_vec();
+        bytes = &bytes[cut..];
+    }
+    let data = [&part.tail[..], bytes].concat();
+    part.tail.clear();
+    match core::str::from_utf8(&data) {
+        Ok(_) => (),
+        Err(e) if e.error_len().is_none() => part.tail = data[e.valid_up_to()..].to_vec(),
+        Err(_) => part.utf8 = false,
+    }
+}
+
+// fill buf unless the file ends first
+fn read_full(file: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut n = 0;
+    while n < buf.len() {
+        match file.read(&mut buf[n..]) {
+            Ok(0) => break,
+            Ok(read) => n += read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(n)
+}
@@ -0,0 +1,135 @@
+//!
+//! Stream shared between threads
+//!
+
+use crate::{Position, RInStream, ReadError, Stats};
+use std::ops::RangeBounds;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Handle to a stream that can be cloned and sent to other threads, like `rin` for any source
+///
+/// Every call locks the stream, so a read is never interleaved with reads of other handles.
+/// Methods without a counterpart here are available through `lock`
+///
+/// ``` rust
+///     use rcin::RInStream;
+///     use std::thread;
+///
+///     let shared = RInStream::from_source(Box::new(&b"1 2 3 4"[..])).into_shared();
+///     let workers: Vec<_> = (0..2)
+///         .map(|_| {
+///             let shared = shared.clone();
+///             // one token per lock, so the workers take turns
+///             thread::spawn(move || std::iter::from_fn(|| shared.read::<i32>()).sum::<i32>())
+///         })
+///         .collect();
+///     let sum: i32 = workers.into_iter().map(|w| w.join().unwrap()).sum();
+///     assert_eq!(sum, 10);
+/// ```
+#[derive(Clone)]
+pub struct SharedRIn {
+    stream: Arc<Mutex<RInStream>>,
+}
+impl RInStream {
+    /// Move the stream behind a handle that can be shared between threads
+    pub fn into_shared(self) -> SharedRIn {
+        SharedRIn {
+            stream: Arc::new(Mutex::new(self)),
+        }
+    }
+}
+impl SharedRIn {
+    /// Lock the stream for a sequence of reads, other handles wait until the guard is dropped
+    pub fn lock(&self) -> MutexGuard<'_, RInStream> {
+        crate::lock_stream(&self.stream)
+    }
+    /// Read char
+    pub fn read_char(&self) -> Option<char> {
+        self.lock().read_char()
+    }
+    /// Read value
+    pub fn read<T: FromStr + 'static>(&self) -> Option<T> {
+        self.lock().read()
+    }
+    /// Read exactly N values into an array
+    pub fn read_array<T: FromStr + 'static, const N: usize>(&self) -> Option<[T; N]> {
+        self.lock().read_array()
+    }
+    /// Fill the slice with values, returns how many were read
+    pub fn read_into_slice<T: FromStr + 'static>(&self, slice: &mut [T]) -> usize {
+        self.lock().read_into_slice(slice)
+    }
+    /// Read n and then n values
+    pub fn read_counted<T: FromStr + 'static>(&self) -> Option<Vec<T>> {
+        self.lock().read_counted()
+    }
+    /// Read a rows x cols matrix
+    pub fn read_matrix<T: FromStr + 'static>(&self, rows: usize, cols: usize) -> Option<Vec<Vec<T>>> {
+        self.lock().read_matrix(rows, cols)
+    }
+    /// Read value and report why it failed
+    pub fn read_checked<T: FromStr + 'static>(&self) -> Result<T, ReadError> {
+        self.lock().read_checked()
+    }
+    /// Read value that has to be within range
+    pub fn read_in_range<T: FromStr + 'static + PartialOrd, R: RangeBounds<T>>(&self, range: R) -> Result<T, ReadError> {
+        self.lock().read_in_range(range)
+    }
+    /// Read all remaining values
+    pub fn read_all<T: FromStr + 'static>(&self) -> Vec<T> {
+        self.lock().read_all()
+    }
+    /// Read all remaining lines
+    pub fn read_all_lines(&self) -> Vec<String> {
+        self.lock().read_all_lines()
+    }
+    /// Read everything left
+    pub fn read_to_string(&self) -> String {
+        self.lock().read_to_string()
+    }
+    /// Next token without consuming it
+    pub fn peek_token(&self) -> Option<String> {
+        self.lock().peek_token().map(str::to_string)
+    }
+    /// Next line without consuming it
+    pub fn peek_line(&self) -> Option<String> {
+        self.lock().peek_line().map(str::to_string)
+    }
+    /// Read line
+    pub fn read_line(&self) -> Option<String> {
+        self.lock().read_line()
+    }
+    /// Read line and report why it failed
+    pub fn read_line_checked(&self) -> Result<String, ReadError> {
+        self.lock().read_line_checked()
+    }
+    /// Read one csv record
+    pub fn read_csv_record(&self, delimiter: char) -> Option<Vec<String>> {
+        self.lock().read_csv_record(delimiter)
+    }
+    /// Skip input until needle, returns false if it wasn't found
+    pub fn skip_until(&self, needle: char, include: bool) -> bool {
+        self.lock().skip_until(needle, include)
+    }
+    /// Skip input until needle, returns false if it wasn't found
+    pub fn skip_until_str(&self, needle: &str, include: bool) -> bool {
+        self.lock().skip_until_str(needle, include)
+    }
+    /// Skip the rest of the line
+    pub fn skip_line(&self) {
+        self.lock().skip_line()
+    }
+    /// Position of the next char
+    pub fn position(&self) -> Position {
+        self.lock().position()
+    }
+    /// Counters of the work done so far
+    pub fn stats(&self) -> Stats {
+        self.lock().stats()
+    }
+    /// Check if the stream has input left
+    pub fn valid(&self) -> bool {
+        self.lock().valid()
+    }
+}
@@ -0,0 +1,289 @@
+//!
+//! Stream internals fed in small pieces, so refills land inside runs and sequences
+//!
+
+use super::*;
+
+// hands out at most step bytes per read
+struct Chunks {
+    data: Vec<u8>,
+    at: usize,
+    step: usize,
+}
+impl ByteSource for Chunks {
+    fn read_bytes(&mut self, buf: &mut [u8]) -> Result<usize, SourceError> {
+        let n = buf.len().min(self.step).min(self.data.len() - self.at);
+        buf[..n].copy_from_slice(&self.data[self.at..self.at + n]);
+        self.at += n;
+        Ok(n)
+    }
+}
+
+fn stream(text: &str, cap: usize, step: usize, whitespace: &WhitespaceMode) -> Stream {
+    let mut stream = Stream::new(
+        Box::new(Chunks {
+            data: text.as_bytes().to_vec(),
+            at: 0,
+            step,
+        }),
+        cap,
+    );
+    stream.whitespace = whitespace.clone();
+    stream
+}
+
+// whitespace runs of every kind and length between tokens, deterministic
+fn mixed_input(tokens: &[&str], seed: u64, vt: bool) -> String {
+    let vt = if vt { "\x0b" } else { " " };
+    let ws = [" ", "\t", "\n", "\r\n", vt, "\x0c"];
+    let mut x = seed;
+    let mut text = String::new();
+    for i in 0..400 {
+        x = x.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        let run = 1 + (x >> 59) as usize * 2;
+        for j in 0..run {
+            // mostly spaces and tabs, so whole 8 byte words come up often
+            let pick = (x >> (j % 50)) as usize % 16;
+            text.push_str(if pick < 12 { ws[pick % 2] } else { ws[pick - 10] });
+        }
+        text.push_str(tokens[i % tokens.len()]);
+    }
+    text
+}
+
+const MODES: [WhitespaceMode; 2] = [WhitespaceMode::AsciiOnly, WhitespaceMode::Unicode];
+const SHAPES: [(usize, usize); 5] = [(8, 3), (13, 5), (16, 16), (17, 100), (64, 7)];
+
+// tokens with their start and the state after each one
+fn tokens(stream: &mut Stream) -> Vec<(String, Position, Position, (usize, usize))> {
+    let mut res = Vec::new();
+    while let Ok(token) = stream.next_token() {
+        res.push((token, stream.token_start, stream.pos, (stream.endings.0, stream.endings.1)));
+    }
+    res
+}
+
+#[test]
+fn tokens_match_the_char_path() {
+    let words = ["ab", "12", "é", "-3", "x\u{a0}y", "日本"];
+    for seed in 0..8 {
+        let text = mixed_input(&words, seed, true);
+        for mode in &MODES {
+            let mut slow = stream(&text, 4096, 4096, mode);
+            slow.mark();
+            let expected = tokens(&mut slow);
+            for &(cap, step) in &SHAPES {
+                let mut fast = stream(&text, cap, step, mode);
+                assert_eq!(tokens(&mut fast), expected, "seed {} {:?} cap {} step {}", seed, mode, cap, step);
+            }
+        }
+    }
+}
+
+#[test]
+fn ints_match_the_char_path() {
+    let numbers = ["7", "-12", "+3", "0", "123456789012", "-9"];
+    for seed in 0..8 {
+        // a vertical tab isn't ascii whitespace, so it would end up in the numbers
+        let text = mixed_input(&numbers, seed, false);
+        let expected: Vec<i64> = numbers.iter().cycle().take(400).map(|n| n.parse().unwrap()).collect();
+        for mode in &MODES {
+            for &(cap, step) in &SHAPES {
+                let mut fast = stream(&text, cap, step, mode);
+                let mut slow = stream(&text, cap, step, mode);
+                slow.mark();
+                let mut unchecked = Vec::new();
+                while let Some(n) = fast.read_int_unchecked::<i64>() {
+                    unchecked.push(n);
+                    assert_eq!(slow.read::<i64>(), Some(n));
+                    assert_eq!(fast.token_start, slow.token_start, "seed {} {:?} cap {}", seed, mode, cap);
+                }
+                assert_eq!(unchecked, expected);
+                assert_eq!(fast.pos, slow.pos);
+                assert_eq!(fast.endings.0 + fast.endings.1, slow.endings.0 + slow.endings.1);
+                let mut bulk = stream(&text, cap, step, mode);
+                assert_eq!(bulk.read_ints_bulk(1000), expected);
+            }
+        }
+    }
+}
+
+#[test]
+fn space_and_tab_words_across_refills() {
+    // 8 byte words start at every offset relative to the refills
+    for lead in 0..8 {
+        let text = format!("{}{}x  \t \t \t \t  \t\t\t\t\t\t\t\ty", "a ".repeat(lead), " \t".repeat(12));
+        let mut fast = stream(&text, 8, 5, &WhitespaceMode::AsciiOnly);
+        let mut slow = stream(&text, 8, 5, &WhitespaceMode::AsciiOnly);
+        slow.mark();
+        assert_eq!(tokens(&mut fast), tokens(&mut slow));
+        let last = tokens(&mut stream(&text, 8, 5, &WhitespaceMode::AsciiOnly)).pop().unwrap();
+        assert_eq!(last.0, "y");
+        assert_eq!(last.1.column, text.len());
+    }
+}
+
+#[test]
+fn crlf_split_by_a_refill_is_counted_once() {
+    // the \r ends one refill and the \n starts the next
+    for step in 1..6 {
+        let mut fast = stream("a\r\n\r\n  \r\nb\n c", 8, step, &WhitespaceMode::AsciiOnly);
+        let found = tokens(&mut fast);
+        assert_eq!(found.iter().map(|t| t.0.as_str()).collect::<Vec<_>>(), ["a", "b", "c"]);
+        assert_eq!(found[1].1.line, 4);
+        assert_eq!((fast.endings.0, fast.endings.1), (1, 3));
+        let mut ints = stream("1\r\n\r\n  \r\n2\n 3", 8, step, &WhitespaceMode::AsciiOnly);
+        assert_eq!(ints.read_ints_bulk(5), vec![1, 2, 3]);
+        assert_eq!((ints.endings.0, ints.endings.1), (1, 3));
+        let mut unchecked = stream("1\r\n2\r\n\r\n3\n", 8, step, &WhitespaceMode::AsciiOnly);
+        let found: Vec<i32> = core::iter::from_fn(|| unchecked.read_int_unchecked()).collect();
+        assert_eq!(found, [1, 2, 3]);
+        assert_eq!((unchecked.endings.0, unchecked.endings.1), (1, 3));
+        assert_eq!((unchecked.pos.line, unchecked.pos.offset), (5, 10));
+    }
+}
+
+#[test]
+fn vertical_tab_follows_the_mode() {
+    let mut ascii = stream("\x0bx 1\x0b2", 8, 8, &WhitespaceMode::AsciiOnly);
+    assert_eq!(ascii.next_token().as_deref(), Ok("\x0bx"));
+    assert_eq!(ascii.read_int_unchecked::<i32>(), Some(1));
+    let mut unicode = stream("\x0bx 1\x0b2", 8, 8, &WhitespaceMode::Unicode);
+    assert_eq!(unicode.next_token().as_deref(), Ok("x"));
+    assert_eq!(unicode.read_ints_bulk(2), vec![1, 2]);
+}
+
+fn keys(text: &str, step: usize) -> Vec<KeyEvent> {
+    let mut stream = stream(text, 16, step, &WhitespaceMode::Unicode);
+    core::iter::from_fn(|| stream.read_key_event()).collect()
+}
+
+#[test]
+fn escape_sequences_decode_to_keys() {
+    let text = "\x1b[H\x1b[1~\x1b[4~\x1bOF\x1b[2~\x1b[5~\x1b[6~\x1b[15~\x1b[24~\x1bOS\x1b[1;5B\x1bx\x1b[9z\x7f\x00\té";
+    let expected = vec![
+        KeyEvent::Home,
+        KeyEvent::Home,
+        KeyEvent::End,
+        KeyEvent::End,
+        KeyEvent::Insert,
+        KeyEvent::PageUp,
+        KeyEvent::PageDown,
+        KeyEvent::F(5),
+        KeyEvent::F(12),
+        KeyEvent::F(4),
+        KeyEvent::Arrow(Direction::Down),
+        KeyEvent::Alt('x'),
+        KeyEvent::Unknown("[9z".into()),
+        KeyEvent::Backspace,
+        KeyEvent::Ctrl(' '),
+        KeyEvent::Tab,
+        KeyEvent::Char('é'),
+    ];
+    assert_eq!(keys(text, 64), expected);
+}
+
+#[test]
+fn escape_alone_in_a_read_is_the_escape_key() {
+    // a sequence split by a read is taken as escape followed by typed chars
+    assert_eq!(keys("\x1b[A", 1), [KeyEvent::Escape, KeyEvent::Char('['), KeyEvent::Char('A')]);
+    assert_eq!(keys("\x1b[A\x1b", 3), [KeyEvent::Arrow(Direction::Up), KeyEvent::Escape]);
+}
+
+#[test]
+fn paste_continues_across_reads() {
+    let found = keys("\x1b[200~é\r\n\x1b[A\x1b[201~q", 2);
+    assert_eq!(found, [KeyEvent::Paste("é\r\n\x1b[A".into()), KeyEvent::Char('q')]);
+    // an unterminated paste ends with the input
+    assert_eq!(keys("\x1b[200~ab", 64), [KeyEvent::Paste("ab".into())]);
+}
+
+#[cfg(feature = "digest")]
+#[test]
+fn digest_survives_a_seek() {
+    use sha2::{Digest, Sha256};
+    let text = "alpha beta gamma\n";
+    let mut stream = Stream::new(Box::new(Seekable(std::io::Cursor::new(text.as_bytes().to_vec()))), 8);
+    stream.digest = Some((Box::new(Sha256::new()), 0));
+    assert_eq!(stream.next_token().as_deref(), Ok("alpha"));
+    let before = stream.consumed;
+    stream.seek(SeekFrom::Start(0)).unwrap();
+    while stream.next_token().is_ok() {}
+    stream.update_digest();
+    let (hasher, _) = stream.digest.as_ref().unwrap();
+    let expected = Sha256::digest(format!("{}{}", &text[..before], text));
+    assert_eq!(hasher.box_clone().finalize()[..], expected[..]);
+}
+
+#[test]
+fn failed_alternative_keeps_io_and_failure_stats() {
+    let mut stream = stream("x 1", 8, 8, &WhitespaceMode::Unicode);
+    assert!(stream.read_either::<i32, u8>().is_none());
+    assert_eq!((stream.stats.refills, stream.stats.bytes_read), (1, 3));
+    assert_eq!((stream.stats.tokens, stream.stats.parse_failures), (0, 1));
+}
+
+#[test]
+fn short_read_chars_keeps_the_refills() {
+    let mut stream = stream("abcdef", 4, 4, &WhitespaceMode::Unicode);
+    assert_eq!(stream.read_chars(10), None);
+    assert_eq!((stream.stats.refills, stream.stats.bytes_read), (2, 6));
+    assert_eq!(stream.read_chars(6).as_deref(), Some("abcdef"));
+}
+
+#[test]
+fn peeks_keep_their_refills() {
+    let mut stream = stream("ab cd", 2, 2, &WhitespaceMode::Unicode);
+    assert_eq!(stream.peek_token(), Some("ab"));
+    assert_eq!(stream.lookahead(4), ['a', 'b', ' ', 'c']);
+    assert_eq!((stream.stats.refills, stream.stats.bytes_read, stream.stats.tokens), (2, 4, 0));
+    assert_eq!(stream.read_token().as_deref(), Some("ab"));
+    assert_eq!(stream.stats.tokens, 1);
+}
+
+#[test]
+fn fast_int_failures_are_reported() {
+    let failed = |stream: &Stream| stream.last_failure.as_ref().map(|f| (f.token.clone(), f.error.clone()));
+    let mut bulk = stream("1 007x -099999999999999999999 +\n", 8, 3, &WhitespaceMode::AsciiOnly);
+    assert_eq!(bulk.read_ints_bulk(5), vec![1]);
+    assert_eq!(failed(&bulk), Some((Some("007x".into()), ReadError::Parse("007x".into()))));
+    assert!(bulk.read_ints_bulk(5).is_empty());
+    let long = "-099999999999999999999".to_string();
+    assert_eq!(failed(&bulk), Some((Some(long.clone()), ReadError::Overflow(long))));
+    assert!(bulk.read_ints_bulk(5).is_empty());
+    assert_eq!(failed(&bulk), Some((Some("+".into()), ReadError::Parse("+".into()))));
+    assert!(bulk.read_ints_bulk(5).is_empty());
+    assert_eq!(failed(&bulk), Some((None, ReadError::Eof)));
+    assert_eq!(bulk.stats.parse_failures, 3);
+
+    let mut unchecked = stream("\r-\r\n5", 8, 8, &WhitespaceMode::AsciiOnly);
+    assert_eq!(unchecked.read_int_unchecked::<i32>(), None);
+    assert_eq!(unchecked.token_start.column, 2);
+    assert_eq!(unchecked.read_token().as_deref(), Some("-"));
+    assert_eq!(unchecked.read_int_unchecked::<i32>(), Some(5));
+    assert_eq!((unchecked.endings.0, unchecked.endings.1), (0, 1));
+}
+
+#[test]
+fn seek_counts_the_position_of_the_target() {
+    let text = "one\ntwo é\r\nthree four\n";
+    let seekable = || Stream::new(Box::new(Seekable(std::io::Cursor::new(text.as_bytes().to_vec()))), 4);
+    let at = |stream: &Stream| (stream.pos.line, stream.pos.column, stream.pos.offset);
+    let mut stream = seekable();
+    stream.seek(SeekFrom::Start(17)).unwrap();
+    assert_eq!(at(&stream), (3, 6, 17));
+    assert_eq!(stream.next_token().as_deref(), Ok("four"));
+    assert_eq!(stream.token_start.column, 7);
+    // backwards without an index counts from the start
+    stream.seek(SeekFrom::Current(-15)).unwrap();
+    assert_eq!(at(&stream), (2, 5, 8));
+    stream.build_line_index().unwrap();
+    assert_eq!(at(&stream), (2, 5, 8));
+    stream.goto_line(3).unwrap();
+    assert_eq!(at(&stream), (3, 1, 12));
+    stream.seek(SeekFrom::Start(5)).unwrap();
+    assert_eq!(at(&stream), (2, 2, 5));
+    // past the end the offset is kept
+    stream.seek(SeekFrom::Start(40)).unwrap();
+    assert_eq!(at(&stream), (4, 1, 40));
+}
@@ -0,0 +1,143 @@
+//!
+//! File source on io_uring that reads the next chunk while the current one is decoded
+//!
+
+use crate::{ByteSource, SourceError};
+use io_uring::{opcode, types, IoUring};
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io::{self, SeekFrom};
+use std::os::unix::io::AsRawFd;
+
+const CHUNK_SIZE: usize = 64 * 1024;
+
+pub(crate) struct UringFile {
+    ring: IoUring,
+    file: File,
+    ready: Vec<u8>, // chunk handed out from start
+    start: usize,
+    ahead: Vec<u8>, // target of the read in flight, untouched while pending
+    pending: bool,
+    next: u64, // file offset of the read in flight or the next one
+    failed: Option<io::Error>, // submit error after a chunk was made ready, returned by the next advance
+}
+
+impl UringFile {
+    pub(crate) fn new(file: File, offset: u64) -> io::Result<Self> {
+        Ok(UringFile {
+            ring: IoUring::new(2)?,
+            file,
+            ready: Vec::new(),
+            start: 0,
+            ahead: vec![0; CHUNK_SIZE],
+            pending: false,
+            next: offset,
+            failed: None,
+        })
+    }
+    fn submit(&mut self) -> io::Result<()> {
+        let fd = types::Fd(self.file.as_raw_fd());
+        let len = u32::try_from(self.ahead.len()).unwrap_or(u32::MAX);
+        let entry = opcode::Read::new(fd, self.ahead.as_mut_ptr(), len).offset(self.next).build();
+        // safety: ahead stays allocated and unused until the read is waited for
+        unsafe {
+            self.ring.submission().push(&entry).map_err(io::Error::other)?;
+        }
+        // queued reads are submitted by the next wait even if this fails
+        self.pending = true;
+        self.ring.submit()?;
+        Ok(())
+    }
+    // bytes read by the read in flight, it stays pending until its completion is reaped
+    fn wait(&mut self) -> io::Result<usize> {
+        let cqe = loop {
+            match self.ring.submit_and_wait(1) {
+                Ok(_) => (),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+            if let Some(cqe) = self.ring.completion().next() {
+                break cqe;
+            }
+        };
+        self.pending = false;
+        match cqe.result() {
+            res if res < 0 => Err(io::Error::from_raw_os_error(-res)),
+            res => Ok(res as usize),
+        }
+    }
+    // make the read chunk ready and start reading the one after it
+    fn advance(&mut self) -> io::Result<usize> {
+        if let Some(e) = self.failed.take() {
+            return Err(e);
+        }
+        if !self.pending {
+            self.submit()?;
+        }
+        let n = self.wait()?;
+        if n == 0 {
+            return Ok(0);
+        }
+        core::mem::swap(&mut self.ready, &mut self.ahead);
+        self.ready.truncate(n);
+        self.ahead.resize(CHUNK_SIZE, 0);
+        self.start = 0;
+        self.next += n as u64;
+        // the ready bytes are handed out first, the error waits for the next advance
+        if let Err(e) = self.submit() {
+            self.failed = Some(e);
+        }
+        Ok(n)
+    }
+    fn cancel(&mut self) -> io::Result<()> {
+        if self.pending {
+            self.wait()?;
+        }
+        self.ready.clear();
+        self.start = 0;
+        self.failed = None;
+        Ok(())
+    }
+}
+
+impl ByteSource for UringFile {
+    fn read_bytes(&mut self, buf: &mut [u8]) -> Result<usize, SourceError> {
+        if self.start == self.ready.len() && self.advance().map_err(|_| SourceError)? == 0 {
+            return Ok(0);
+        }
+        let n = buf.len().min(self.ready.len() - self.start);
+        buf[..n].copy_from_slice(&self.ready[self.start..self.start + n]);
+        self.start += n;
+        Ok(n)
+    }
+    fn seek(&mut self, pos: SeekFrom) -> Option<io::Result<u64>> {
+        let current = self.next - (self.ready.len() - self.start) as u64;
+        let target = match pos {
+            SeekFrom::Start(n) => Some(n),
+            SeekFrom::Current(d) => current.checked_add_signed(d),
+            SeekFrom::End(d) => match self.file.metadata() {
+                Ok(meta) => meta.len().checked_add_signed(d),
+                Err(e) => return Some(Err(e)),
+            },
+        };
+        let target = match target {
+            Some(target) => target,
+            None => return Some(Err(io::Error::new(io::ErrorKind::InvalidInput, "seek before the start"))),
+        };
+        if let Err(e) = self.cancel() {
+            return Some(Err(e));
+        }
+        self.next = target;
+        Some(Ok(target))
+    }
+}
+
+impl Drop for UringFile {
+    // the kernel must not write into ahead after it's freed
+    fn drop(&mut self) {
+        if self.pending && self.wait().is_err() {
+            // the read can still complete, so its buffer is leaked instead of freed
+            core::mem::forget(core::mem::take(&mut self.ahead));
+        }
+    }
+}